@@ -40,7 +40,7 @@ fn main() {
     let records = std::env::args()
         .skip(1)
         .collect::<Vec<_>>()
-        .get(0)
+        .first()
         .cloned()
         .expect("Missing text arg.");
 
@@ -0,0 +1,107 @@
+//! Lock-free aggregation of a per-record quantity.
+use std::{
+    fmt::{self, Display},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Summary statistics of the values given to [`ProgLog::record_value`](crate::ProgLog::record_value).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ValueStats {
+    /// The number of values seen.
+    pub count: u64,
+    /// The sum of the values seen.
+    pub sum: f64,
+    /// The smallest value seen, `f64::INFINITY` if none have been seen.
+    pub min: f64,
+    /// The largest value seen, `f64::NEG_INFINITY` if none have been seen.
+    pub max: f64,
+}
+
+impl ValueStats {
+    /// The mean of the values seen, `None` if none have been seen.
+    pub fn mean(&self) -> Option<f64> {
+        if self.count > 0 {
+            Some(self.sum / self.count as f64)
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for ValueStats {
+    /// Formats as `sum {sum} mean {mean} min {min} max {max}`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sum {} mean {:.1} min {} max {}",
+            self.sum,
+            self.mean().unwrap_or(f64::NAN),
+            self.min,
+            self.max
+        )
+    }
+}
+
+/// Lock-free sum/min/max of `f64` values, stored as bits in atomics.
+#[derive(Debug)]
+pub(crate) struct Aggregate {
+    count: AtomicU64,
+    sum: AtomicU64,
+    min: AtomicU64,
+    max: AtomicU64,
+}
+
+impl Aggregate {
+    pub(crate) fn new() -> Self {
+        Self {
+            count: AtomicU64::new(0),
+            sum: AtomicU64::new(0.0_f64.to_bits()),
+            min: AtomicU64::new(f64::INFINITY.to_bits()),
+            max: AtomicU64::new(f64::NEG_INFINITY.to_bits()),
+        }
+    }
+
+    /// Add a value, `NaN`s are ignored.
+    pub(crate) fn add(&self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        update(&self.sum, |sum| Some(sum + value));
+        update(&self.min, |min| (value < min).then_some(value));
+        update(&self.max, |max| (value > max).then_some(value));
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The stats of the values seen so far.
+    pub(crate) fn stats(&self) -> ValueStats {
+        ValueStats {
+            count: self.count.load(Ordering::Relaxed),
+            sum: f64::from_bits(self.sum.load(Ordering::Relaxed)),
+            min: f64::from_bits(self.min.load(Ordering::Relaxed)),
+            max: f64::from_bits(self.max.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// The stats of the values seen so far, resetting the aggregate.
+    ///
+    /// Values added concurrently with a reset may be split across the old and new stats.
+    pub(crate) fn take(&self) -> ValueStats {
+        ValueStats {
+            count: self.count.swap(0, Ordering::Relaxed),
+            sum: f64::from_bits(self.sum.swap(0.0_f64.to_bits(), Ordering::Relaxed)),
+            min: f64::from_bits(self.min.swap(f64::INFINITY.to_bits(), Ordering::Relaxed)),
+            max: f64::from_bits(
+                self.max
+                    .swap(f64::NEG_INFINITY.to_bits(), Ordering::Relaxed),
+            ),
+        }
+    }
+}
+
+/// Apply `f` to the `f64` stored in `atomic` until it sticks, or `f` returns `None`.
+pub(crate) fn update(atomic: &AtomicU64, f: impl Fn(f64) -> Option<f64>) {
+    let _ = atomic.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+        f(f64::from_bits(bits)).map(f64::to_bits)
+    });
+}
@@ -0,0 +1,306 @@
+//! Channels that count the items sent and received through two [`ProgLog`]s.
+//!
+//! Each message of either logger reports the number of items in flight, sent but not yet
+//! received, so that backpressure between pipeline stages shows up in the progress logs:
+//!
+//! ```text
+//! [sent] Processed 2000000 records; 1024 in flight
+//! ```
+//!
+//! ```rust
+//! use proglog::{channel, ProgLogBuilder};
+//!
+//! let (tx, rx) = channel::sync_channel(
+//!     1024,
+//!     ProgLogBuilder::new().name("sent").build(),
+//!     ProgLogBuilder::new().name("received").build(),
+//! );
+//! std::thread::spawn(move || {
+//!     for i in 0..10_000 {
+//!         tx.send(i).unwrap();
+//!     }
+//! });
+//! let sum: u64 = rx.into_iter().sum();
+//! ```
+//!
+//! With the `crossbeam` feature, `bounded` and `unbounded` do the same for `crossbeam_channel`.
+use crate::ProgLog;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, RecvError, RecvTimeoutError, SendError, TryRecvError, TrySendError},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// The sending half of an instrumented channel, counting each item sent.
+pub struct ProgressSender<S> {
+    inner: S,
+    log: Arc<ProgLog>,
+}
+
+/// The receiving half of an instrumented channel, counting each item received.
+pub struct ProgressReceiver<R> {
+    inner: R,
+    log: Arc<ProgLog>,
+}
+
+/// Wrap the halves of an existing channel, counting the items sent with `sent` and the items
+/// received with `received`.
+pub fn instrument<S, R>(
+    sender: S,
+    receiver: R,
+    sent: ProgLog,
+    received: ProgLog,
+) -> (ProgressSender<S>, ProgressReceiver<R>) {
+    let counters = (
+        Arc::clone(&sent.inner.counter),
+        Arc::clone(&received.inner.counter),
+    );
+    let _ = sent.inner.in_flight.set(counters.clone());
+    let _ = received.inner.in_flight.set(counters);
+    (
+        ProgressSender {
+            inner: sender,
+            log: Arc::new(sent),
+        },
+        ProgressReceiver {
+            inner: receiver,
+            log: Arc::new(received),
+        },
+    )
+}
+
+/// An instrumented [`mpsc::channel`].
+pub fn channel<T>(
+    sent: ProgLog,
+    received: ProgLog,
+) -> (
+    ProgressSender<mpsc::Sender<T>>,
+    ProgressReceiver<mpsc::Receiver<T>>,
+) {
+    let (sender, receiver) = mpsc::channel();
+    instrument(sender, receiver, sent, received)
+}
+
+/// An instrumented [`mpsc::sync_channel`].
+pub fn sync_channel<T>(
+    bound: usize,
+    sent: ProgLog,
+    received: ProgLog,
+) -> (
+    ProgressSender<mpsc::SyncSender<T>>,
+    ProgressReceiver<mpsc::Receiver<T>>,
+) {
+    let (sender, receiver) = mpsc::sync_channel(bound);
+    instrument(sender, receiver, sent, received)
+}
+
+/// The number of items sent but not yet received.
+pub(crate) fn in_flight((sent, received): &(Arc<AtomicU64>, Arc<AtomicU64>)) -> u64 {
+    // An item can be counted as received before it is counted as sent.
+    let received = received.load(Ordering::Relaxed);
+    sent.load(Ordering::Relaxed).saturating_sub(received)
+}
+
+impl<S> ProgressSender<S> {
+    /// The logger counting the items sent.
+    pub fn log(&self) -> &ProgLog {
+        &self.log
+    }
+
+    /// The number of items sent but not yet received.
+    pub fn in_flight(&self) -> u64 {
+        self.log.inner.in_flight.get().map_or(0, in_flight)
+    }
+
+    /// Count an item as sent if `result` is `Ok`.
+    fn count<E>(&self, result: Result<(), E>) -> Result<(), E> {
+        if result.is_ok() {
+            self.log.record();
+        }
+        result
+    }
+}
+
+impl<S: Clone> Clone for ProgressSender<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            log: Arc::clone(&self.log),
+        }
+    }
+}
+
+impl<T> ProgressSender<mpsc::Sender<T>> {
+    /// See [`mpsc::Sender::send`].
+    pub fn send(&self, item: T) -> Result<(), SendError<T>> {
+        self.count(self.inner.send(item))
+    }
+}
+
+impl<T> ProgressSender<mpsc::SyncSender<T>> {
+    /// See [`mpsc::SyncSender::send`].
+    pub fn send(&self, item: T) -> Result<(), SendError<T>> {
+        self.count(self.inner.send(item))
+    }
+
+    /// See [`mpsc::SyncSender::try_send`].
+    pub fn try_send(&self, item: T) -> Result<(), TrySendError<T>> {
+        self.count(self.inner.try_send(item))
+    }
+}
+
+impl<R> ProgressReceiver<R> {
+    /// The logger counting the items received.
+    pub fn log(&self) -> &ProgLog {
+        &self.log
+    }
+
+    /// The number of items sent but not yet received.
+    pub fn in_flight(&self) -> u64 {
+        self.log.inner.in_flight.get().map_or(0, in_flight)
+    }
+
+    /// Count an item as received if `result` is `Ok`.
+    fn count<T, E>(&self, result: Result<T, E>) -> Result<T, E> {
+        if result.is_ok() {
+            self.log.record();
+        }
+        result
+    }
+}
+
+impl<T> ProgressReceiver<mpsc::Receiver<T>> {
+    /// See [`mpsc::Receiver::recv`].
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.count(self.inner.recv())
+    }
+
+    /// See [`mpsc::Receiver::try_recv`].
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.count(self.inner.try_recv())
+    }
+
+    /// See [`mpsc::Receiver::recv_timeout`].
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.count(self.inner.recv_timeout(timeout))
+    }
+
+    /// Iterate over the items received until the channel is disconnected.
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(|| self.recv().ok())
+    }
+}
+
+impl<T> IntoIterator for ProgressReceiver<mpsc::Receiver<T>> {
+    type Item = T;
+    type IntoIter = IntoIter<mpsc::Receiver<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { receiver: self }
+    }
+}
+
+/// An iterator over the items received until the channel is disconnected.
+pub struct IntoIter<R> {
+    receiver: ProgressReceiver<R>,
+}
+
+impl<T> Iterator for IntoIter<mpsc::Receiver<T>> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.recv().ok()
+    }
+}
+
+#[cfg(feature = "crossbeam")]
+mod crossbeam {
+    use super::{instrument, IntoIter, ProgressReceiver, ProgressSender};
+    use crate::ProgLog;
+    use crossbeam_channel::{
+        Receiver, RecvError, RecvTimeoutError, SendError, SendTimeoutError, Sender, TryRecvError,
+        TrySendError,
+    };
+    use std::time::Duration;
+
+    /// An instrumented [`crossbeam_channel::bounded`].
+    pub fn bounded<T>(
+        cap: usize,
+        sent: ProgLog,
+        received: ProgLog,
+    ) -> (ProgressSender<Sender<T>>, ProgressReceiver<Receiver<T>>) {
+        let (sender, receiver) = crossbeam_channel::bounded(cap);
+        instrument(sender, receiver, sent, received)
+    }
+
+    /// An instrumented [`crossbeam_channel::unbounded`].
+    pub fn unbounded<T>(
+        sent: ProgLog,
+        received: ProgLog,
+    ) -> (ProgressSender<Sender<T>>, ProgressReceiver<Receiver<T>>) {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        instrument(sender, receiver, sent, received)
+    }
+
+    impl<T> ProgressSender<Sender<T>> {
+        /// See [`Sender::send`].
+        pub fn send(&self, item: T) -> Result<(), SendError<T>> {
+            self.count(self.inner.send(item))
+        }
+
+        /// See [`Sender::try_send`].
+        pub fn try_send(&self, item: T) -> Result<(), TrySendError<T>> {
+            self.count(self.inner.try_send(item))
+        }
+
+        /// See [`Sender::send_timeout`].
+        pub fn send_timeout(&self, item: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+            self.count(self.inner.send_timeout(item, timeout))
+        }
+    }
+
+    impl<T> ProgressReceiver<Receiver<T>> {
+        /// See [`Receiver::recv`].
+        pub fn recv(&self) -> Result<T, RecvError> {
+            self.count(self.inner.recv())
+        }
+
+        /// See [`Receiver::try_recv`].
+        pub fn try_recv(&self) -> Result<T, TryRecvError> {
+            self.count(self.inner.try_recv())
+        }
+
+        /// See [`Receiver::recv_timeout`].
+        pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+            self.count(self.inner.recv_timeout(timeout))
+        }
+
+        /// Iterate over the items received until the channel is disconnected.
+        pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+            std::iter::from_fn(|| self.recv().ok())
+        }
+    }
+
+    impl<T> IntoIterator for ProgressReceiver<Receiver<T>> {
+        type Item = T;
+        type IntoIter = IntoIter<Receiver<T>>;
+
+        fn into_iter(self) -> Self::IntoIter {
+            IntoIter { receiver: self }
+        }
+    }
+
+    impl<T> Iterator for IntoIter<Receiver<T>> {
+        type Item = T;
+
+        fn next(&mut self) -> Option<T> {
+            self.receiver.recv().ok()
+        }
+    }
+}
+
+#[cfg(feature = "crossbeam")]
+pub use self::crossbeam::{bounded, unbounded};
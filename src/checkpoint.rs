@@ -0,0 +1,137 @@
+//! Persisting the count of a logger so that it can resume after a restart.
+//!
+//! A checkpoint is a small text file with one `{key} {value}` pair per line:
+//!
+//! ```text
+//! count 27400000
+//! total 100000000
+//! ```
+use crate::{lock, ticker::Ticker, Inner};
+use log::warn;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+/// The state saved in a checkpoint file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Checkpoint {
+    pub(crate) count: u64,
+    pub(crate) total: Option<u64>,
+}
+
+impl Checkpoint {
+    fn parse(text: &str) -> Option<Self> {
+        let mut count = None;
+        let mut total = None;
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let (key, value) = line.split_once(' ')?;
+            let value = value.trim().parse().ok()?;
+            match key {
+                "count" => count = Some(value),
+                "total" => total = Some(value),
+                _ => return None,
+            }
+        }
+        Some(Self {
+            count: count?,
+            total,
+        })
+    }
+
+    fn to_text(self) -> String {
+        let mut text = format!("count {}\n", self.count);
+        if let Some(total) = self.total {
+            text.push_str(&format!("total {}\n", total));
+        }
+        text
+    }
+}
+
+/// Read the checkpoint at `path`, `None` if there is no such file.
+pub(crate) fn read(path: &Path) -> io::Result<Option<Checkpoint>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    Checkpoint::parse(&text).map(Some).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "expected `count {n}` and optionally `total {n}` lines",
+        )
+    })
+}
+
+/// Write `checkpoint` to `path`, via a temporary file that is renamed over `path` so that a
+/// crash never leaves a partially written checkpoint behind.
+pub(crate) fn write(path: &Path, checkpoint: Checkpoint) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    fs::write(&tmp, checkpoint.to_text())?;
+    fs::rename(&tmp, path)
+}
+
+impl Inner {
+    /// Write the current count to the checkpoint file, if enabled, warning on failure.
+    pub(crate) fn save_checkpoint(&self) {
+        let Some(path) = &self.checkpoint else {
+            return;
+        };
+        // Serialize writers, so that an older count never replaces a newer one.
+        let _guard = lock(&self.checkpoint_lock);
+        let checkpoint = Checkpoint {
+            count: self.count(),
+            total: self.total,
+        };
+        if let Err(err) = write(path, checkpoint) {
+            warn!(
+                "[{}] Failed to write checkpoint {}: {}",
+                self.name,
+                path.display(),
+                err
+            );
+        }
+    }
+}
+
+/// Write a checkpoint every `period`.
+pub(crate) fn checkpointer(inner: Arc<Inner>, period: Duration) -> Ticker {
+    Ticker::spawn(
+        format!("proglog-checkpoint-{}", inner.name),
+        period,
+        move || inner.save_checkpoint(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint() {
+        let path = std::env::temp_dir().join(format!("proglog-{}.checkpoint", std::process::id()));
+        assert_eq!(read(&path).unwrap(), None);
+        for checkpoint in [
+            Checkpoint {
+                count: 27_400_000,
+                total: Some(100_000_000),
+            },
+            Checkpoint {
+                count: 5,
+                total: None,
+            },
+        ] {
+            write(&path, checkpoint).unwrap();
+            assert_eq!(read(&path).unwrap(), Some(checkpoint));
+        }
+        fs::write(&path, "count lots\n").unwrap();
+        assert_eq!(read(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(Checkpoint::parse("total 10\n"), None);
+        assert_eq!(Checkpoint::parse("count 1\nspeed 2\n"), None);
+    }
+}
@@ -0,0 +1,56 @@
+//! Bookkeeping of the child loggers created with [`ProgLog::child`](crate::ProgLog::child).
+use crate::lock;
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+/// The counters of the live children of a logger, and the final counts of the dropped ones.
+pub(crate) struct Children {
+    live: Mutex<Vec<Arc<AtomicU64>>>,
+    done: AtomicU64,
+    /// Set once the parent is dropped, after which children are no longer rolled into it.
+    closed: AtomicBool,
+}
+
+impl Children {
+    pub(crate) fn new() -> Self {
+        Self {
+            live: Mutex::new(Vec::new()),
+            done: AtomicU64::new(0),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    /// Start tracking the counter of a new child.
+    pub(crate) fn add(&self, counter: Arc<AtomicU64>) {
+        lock(&self.live).push(counter);
+    }
+
+    /// Stop tracking the counter of a dropped child, rolling its final count into the total.
+    ///
+    /// Returns `false`, without rolling up the count, if the parent has already been dropped.
+    pub(crate) fn finish(&self, counter: &Arc<AtomicU64>) -> bool {
+        let mut live = lock(&self.live);
+        live.retain(|child| !Arc::ptr_eq(child, counter));
+        if self.closed.load(Ordering::Relaxed) {
+            return false;
+        }
+        self.done
+            .fetch_add(counter.load(Ordering::Relaxed), Ordering::Relaxed);
+        true
+    }
+
+    /// Stop rolling the children into the parent, called when the parent is dropped.
+    pub(crate) fn close(&self) {
+        let _live = lock(&self.live);
+        self.closed.store(true, Ordering::Relaxed);
+    }
+
+    /// The number of items seen by the live children, and by all children.
+    pub(crate) fn counts(&self) -> (u64, u64) {
+        let live = lock(&self.live);
+        let in_progress: u64 = live.iter().map(|child| child.load(Ordering::Relaxed)).sum();
+        (in_progress, in_progress + self.done.load(Ordering::Relaxed))
+    }
+}
@@ -0,0 +1,115 @@
+//! Reusable command line arguments for configuring a [`ProgLogBuilder`].
+#[cfg(feature = "pretty_counts")]
+use crate::CountFormatterKind;
+use crate::{env::parse_duration, ProgLogBuilder};
+use log::Level;
+use std::time::Duration;
+
+/// Progress arguments to flatten into a `clap` command line.
+///
+/// ```rust
+/// use clap::Parser;
+/// use proglog::{ProgLogBuilder, ProgressArgs};
+///
+/// #[derive(Parser)]
+/// struct Cli {
+///     #[command(flatten)]
+///     progress: ProgressArgs,
+/// }
+///
+/// let cli = Cli::parse_from(["tool", "--progress-unit", "1000"]);
+/// let logger = cli.progress.apply(ProgLogBuilder::new().noun("reads")).build();
+/// ```
+///
+/// Options that are not given keep the value set on the builder. If the builder also uses
+/// [`ProgLogBuilder::from_env`], the environment variables take precedence over these arguments.
+#[derive(Debug, Clone, Default, PartialEq, clap::Args)]
+pub struct ProgressArgs {
+    /// Emit a progress message every N items.
+    #[arg(
+        long = "progress-unit",
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub unit: Option<u64>,
+    /// Adjust the progress unit to emit a message about this often, i.e. `30s` or `5m`.
+    #[arg(long = "progress-interval", value_name = "DURATION", value_parser = parse_interval)]
+    pub interval: Option<Duration>,
+    /// The log level of progress messages, one of error, warn, info, debug or trace.
+    #[arg(long = "progress-level", value_name = "LEVEL")]
+    pub level: Option<Level>,
+    /// How to format counts in progress messages.
+    #[cfg(feature = "pretty_counts")]
+    #[arg(long = "progress-format", value_name = "FORMAT", value_enum)]
+    pub format: Option<CountFormatterKind>,
+    /// Disable progress messages.
+    #[arg(long = "no-progress")]
+    pub disable: bool,
+}
+
+impl ProgressArgs {
+    /// Apply the arguments that were given to `builder`.
+    pub fn apply(&self, mut builder: ProgLogBuilder) -> ProgLogBuilder {
+        if let Some(unit) = self.unit {
+            builder = builder.unit(unit);
+        }
+        if let Some(interval) = self.interval {
+            builder = builder.target_interval(interval);
+        }
+        if let Some(level) = self.level {
+            builder = builder.level(level);
+        }
+        #[cfg(feature = "pretty_counts")]
+        if let Some(formatter) = self.format {
+            builder = builder.count_formatter(formatter);
+        }
+        if self.disable {
+            builder = builder.muted(true);
+        }
+        builder
+    }
+}
+
+fn parse_interval(value: &str) -> Result<Duration, String> {
+    parse_duration(value.trim())
+        .ok_or_else(|| format!("`{}` is not a duration, i.e. `30s`, `5m` or `1h`", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        progress: ProgressArgs,
+    }
+
+    #[test]
+    fn test_progress_args() {
+        let cli = Cli::try_parse_from(["tool"]).unwrap();
+        assert_eq!(cli.progress, ProgressArgs::default());
+        let builder = cli.progress.apply(ProgLogBuilder::new().unit(5));
+        assert_eq!((builder.unit, builder.muted), (5, false));
+
+        let cli = Cli::try_parse_from([
+            "tool",
+            "--progress-unit",
+            "1000",
+            "--progress-interval",
+            "30s",
+            "--progress-level",
+            "debug",
+            "--no-progress",
+        ])
+        .unwrap();
+        let builder = cli.progress.apply(ProgLogBuilder::new());
+        assert_eq!((builder.unit, builder.level), (1000, Level::Debug));
+        assert_eq!(builder.target_interval, Some(Duration::from_secs(30)));
+        assert!(builder.muted);
+
+        assert!(Cli::try_parse_from(["tool", "--progress-unit", "0"]).is_err());
+        assert!(Cli::try_parse_from(["tool", "--progress-interval", "soon"]).is_err());
+    }
+}
@@ -0,0 +1,310 @@
+//! Deserializable configuration of a [`ProgLogBuilder`].
+#[cfg(feature = "pretty_counts")]
+use crate::CountFormatterKind;
+use crate::{env::parse_duration, ProgLogBuilder};
+use log::Level;
+use serde::{Deserialize, Deserializer};
+use std::{collections::BTreeMap, path::PathBuf, time::Duration};
+
+/// The options of a [`ProgLogBuilder`], i.e. read from a TOML or YAML file.
+///
+/// Every field is optional and falls back to the builder default. Durations are given as a number
+/// of seconds or a string such as `250ms`, `30s`, `5m` or `1h`.
+///
+/// ```toml
+/// name = "reads"
+/// noun = "reads"
+/// unit = 1_000_000
+/// level = "info"
+/// tiers = [{ unit = 10_000, level = "debug" }]
+/// heartbeat = "5m"
+/// ```
+///
+/// Convert it into a builder with [`ProgLogBuilder::from`], see also [`ProgLogConfigs`] for
+/// configuring several loggers at once.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProgLogConfig {
+    /// See [`ProgLogBuilder::name`].
+    pub name: Option<String>,
+    /// See [`ProgLogBuilder::noun`].
+    pub noun: Option<String>,
+    /// See [`ProgLogBuilder::verb`].
+    pub verb: Option<String>,
+    /// See [`ProgLogBuilder::unit`], must be at least `1`.
+    #[serde(deserialize_with = "unit")]
+    pub unit: Option<u64>,
+    /// See [`ProgLogBuilder::level`].
+    pub level: Option<Level>,
+    /// See [`ProgLogBuilder::tier`].
+    pub tiers: Vec<TierConfig>,
+    /// See [`ProgLogBuilder::count_formatter`], i.e. `comma` or `underscore`.
+    #[cfg(feature = "pretty_counts")]
+    pub count_formatter: Option<CountFormatterKind>,
+    /// See [`ProgLogBuilder::total`].
+    pub total: Option<u64>,
+    /// See [`ProgLogBuilder::rate_half_life`].
+    #[serde(deserialize_with = "duration")]
+    pub rate_half_life: Option<Duration>,
+    /// See [`ProgLogBuilder::counter`].
+    pub counters: Vec<String>,
+    /// See [`ProgLogBuilder::sample_errors`].
+    pub sample_errors: Option<usize>,
+    /// See [`ProgLogBuilder::value_name`].
+    pub value_name: Option<String>,
+    /// See [`ProgLogBuilder::top_keys`].
+    pub top_keys: Option<usize>,
+    /// See [`ProgLogBuilder::top_keys_every`].
+    pub top_keys_every: Option<u64>,
+    /// See [`ProgLogBuilder::stall_timeout`].
+    #[serde(deserialize_with = "duration")]
+    pub stall_timeout: Option<Duration>,
+    /// See [`ProgLogBuilder::heartbeat`].
+    #[serde(deserialize_with = "duration")]
+    pub heartbeat: Option<Duration>,
+    /// See [`ProgLogBuilder::min_rate`].
+    pub min_rate: Option<f64>,
+    /// See [`ProgLogBuilder::min_rate_of_peak`].
+    pub min_rate_of_peak: Option<f64>,
+    /// See [`ProgLogBuilder::target_interval`].
+    #[serde(deserialize_with = "duration")]
+    pub target_interval: Option<Duration>,
+    /// See [`ProgLogBuilder::child_noun`].
+    pub child_noun: Option<String>,
+    /// See [`ProgLogBuilder::child_unit`], must be at least `1`.
+    #[serde(deserialize_with = "unit")]
+    pub child_unit: Option<u64>,
+    /// See [`ProgLogBuilder::weighted_total`].
+    pub weighted_total: Option<f64>,
+    /// See [`ProgLogBuilder::initial_count`].
+    pub initial_count: Option<u64>,
+    /// See [`ProgLogBuilder::checkpoint`].
+    pub checkpoint: Option<PathBuf>,
+    /// See [`ProgLogBuilder::checkpoint_period`].
+    #[serde(deserialize_with = "duration")]
+    pub checkpoint_period: Option<Duration>,
+    /// See [`ProgLogBuilder::resume_from`].
+    pub resume_from: Option<PathBuf>,
+    /// See [`ProgLogBuilder::from_env`].
+    pub from_env: bool,
+    /// See [`ProgLogBuilder::muted`].
+    pub muted: bool,
+}
+
+/// An additional cadence, see [`ProgLogBuilder::tier`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TierConfig {
+    /// How many items must be seen before emitting a message at `level`.
+    pub unit: u64,
+    /// The [`log::Level`] of the messages.
+    pub level: Level,
+}
+
+impl From<ProgLogConfig> for ProgLogBuilder {
+    fn from(config: ProgLogConfig) -> Self {
+        let mut builder = ProgLogBuilder::new();
+        if let Some(name) = config.name {
+            builder = builder.name(name);
+        }
+        if let Some(noun) = config.noun {
+            builder = builder.noun(noun);
+        }
+        if let Some(verb) = config.verb {
+            builder = builder.verb(verb);
+        }
+        if let Some(unit) = config.unit {
+            builder = builder.unit(unit);
+        }
+        if let Some(level) = config.level {
+            builder = builder.level(level);
+        }
+        for tier in config.tiers {
+            builder = builder.tier(tier.unit, tier.level);
+        }
+        #[cfg(feature = "pretty_counts")]
+        if let Some(formatter) = config.count_formatter {
+            builder = builder.count_formatter(formatter);
+        }
+        if let Some(total) = config.total {
+            builder = builder.total(total);
+        }
+        if let Some(half_life) = config.rate_half_life {
+            builder = builder.rate_half_life(half_life);
+        }
+        for counter in config.counters {
+            builder = builder.counter(counter);
+        }
+        if let Some(sample_errors) = config.sample_errors {
+            builder = builder.sample_errors(sample_errors);
+        }
+        if let Some(value_name) = config.value_name {
+            builder = builder.value_name(value_name);
+        }
+        if let Some(k) = config.top_keys {
+            builder = builder.top_keys(k);
+        }
+        if let Some(messages) = config.top_keys_every {
+            builder = builder.top_keys_every(messages);
+        }
+        if let Some(timeout) = config.stall_timeout {
+            builder = builder.stall_timeout(timeout);
+        }
+        if let Some(period) = config.heartbeat {
+            builder = builder.heartbeat(period);
+        }
+        if let Some(rate) = config.min_rate {
+            builder = builder.min_rate(rate);
+        }
+        if let Some(fraction) = config.min_rate_of_peak {
+            builder = builder.min_rate_of_peak(fraction);
+        }
+        if let Some(interval) = config.target_interval {
+            builder = builder.target_interval(interval);
+        }
+        if let Some(noun) = config.child_noun {
+            builder = builder.child_noun(noun);
+        }
+        if let Some(unit) = config.child_unit {
+            builder = builder.child_unit(unit);
+        }
+        if let Some(total) = config.weighted_total {
+            builder = builder.weighted_total(total);
+        }
+        if let Some(count) = config.initial_count {
+            builder = builder.initial_count(count);
+        }
+        if let Some(path) = config.checkpoint {
+            builder = builder.checkpoint(path);
+        }
+        if let Some(period) = config.checkpoint_period {
+            builder = builder.checkpoint_period(period);
+        }
+        if let Some(path) = config.resume_from {
+            builder = builder.resume_from(path);
+        }
+        if config.from_env {
+            builder = builder.from_env();
+        }
+        builder.muted(config.muted)
+    }
+}
+
+/// The configuration of every logger in an application, keyed by logger name.
+///
+/// ```toml
+/// [reads]
+/// unit = 1_000_000
+///
+/// [variants]
+/// noun = "variants"
+/// level = "debug"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct ProgLogConfigs(pub BTreeMap<String, ProgLogConfig>);
+
+impl ProgLogConfigs {
+    /// The configuration of the logger `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&ProgLogConfig> {
+        self.0.get(name)
+    }
+
+    /// A builder for the logger `name`, using its configuration if there is one.
+    ///
+    /// The logger is always named `name`, even if the configuration sets another `name`.
+    pub fn builder(&self, name: &str) -> ProgLogBuilder {
+        self.get(name)
+            .cloned()
+            .map(ProgLogBuilder::from)
+            .unwrap_or_default()
+            .name(name)
+    }
+}
+
+/// Deserialize a unit, rejecting `0` like the environment variables and command line arguments do.
+fn unit<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match u64::deserialize(deserializer)? {
+        0 => Err(serde::de::Error::custom("expected a unit of at least 1")),
+        unit => Ok(Some(unit)),
+    }
+}
+
+/// Deserialize an optional duration from a number of seconds or a string such as `30s`.
+fn duration<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Secs(f64),
+        Human(String),
+    }
+    let parsed = match Repr::deserialize(deserializer)? {
+        // Zero is rejected like `parse_duration` does, it would make the timer threads spin.
+        Repr::Secs(secs) => Duration::try_from_secs_f64(secs)
+            .ok()
+            .filter(|duration| !duration.is_zero()),
+        Repr::Human(value) => parse_duration(value.trim()),
+    };
+    parsed
+        .map(Some)
+        .ok_or_else(|| serde::de::Error::custom("expected a duration, i.e. `30s`, `5m` or `1h`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config() {
+        let configs: ProgLogConfigs = toml::from_str(
+            r#"
+            [reads]
+            noun = "reads"
+            unit = 1_000
+            level = "debug"
+            tiers = [{ unit = 100, level = "trace" }]
+            heartbeat = "5m"
+            stall_timeout = 90
+            counters = ["kept", "filtered"]
+
+            [variants]
+            name = "ignored"
+            "#,
+        )
+        .unwrap();
+        let reads = configs.get("reads").unwrap();
+        assert_eq!(reads.unit, Some(1_000));
+        assert_eq!(reads.level, Some(Level::Debug));
+        assert_eq!(
+            reads.tiers,
+            vec![TierConfig {
+                unit: 100,
+                level: Level::Trace
+            }]
+        );
+        assert_eq!(reads.heartbeat, Some(Duration::from_secs(300)));
+        assert_eq!(reads.stall_timeout, Some(Duration::from_secs(90)));
+        assert_eq!(reads.rate_half_life, None);
+
+        let builder = configs.builder("reads");
+        assert_eq!(builder.name, "reads");
+        assert_eq!(builder.noun, "reads");
+        assert_eq!((builder.unit, builder.level), (1_000, Level::Debug));
+        assert_eq!(builder.counters, vec!["kept", "filtered"]);
+        assert_eq!(configs.builder("variants").name, "variants");
+        assert_eq!(configs.builder("other").unit, ProgLogBuilder::new().unit);
+
+        assert!(toml::from_str::<ProgLogConfig>("heartbeat = \"soon\"").is_err());
+        assert!(toml::from_str::<ProgLogConfig>("units = 5").is_err());
+        assert!(toml::from_str::<ProgLogConfig>("unit = 0").is_err());
+        assert!(toml::from_str::<ProgLogConfig>("child_unit = 0").is_err());
+        assert!(toml::from_str::<ProgLogConfig>("heartbeat = 0").is_err());
+        assert!(toml::from_str::<ProgLogConfig>("heartbeat = \"0s\"").is_err());
+    }
+}
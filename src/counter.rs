@@ -0,0 +1,45 @@
+//! Additional named counters for a [`ProgLog`](crate::ProgLog).
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// An additional named counter reported alongside the main count of a [`ProgLog`](crate::ProgLog).
+///
+/// Counters are registered with [`ProgLogBuilder::counter`](crate::ProgLogBuilder::counter) and
+/// retrieved with [`ProgLog::counter`](crate::ProgLog::counter). Incrementing a counter never
+/// emits a message on its own.
+#[derive(Debug)]
+pub struct Counter {
+    name: String,
+    counter: AtomicU64,
+}
+
+impl Counter {
+    pub(crate) fn new(name: String) -> Self {
+        Self {
+            name,
+            counter: AtomicU64::new(0),
+        }
+    }
+
+    /// The name of the counter.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the number of items counted so far.
+    ///
+    /// This should be treated with some caution as it is using the
+    /// atomic load with [`Ordering::Relaxed`].
+    pub fn seen(&self) -> u64 {
+        self.counter.load(Ordering::Relaxed)
+    }
+
+    /// Increment the counter by 1.
+    pub fn record(&self) {
+        self.record_count(1);
+    }
+
+    /// Increment the counter by `count`.
+    pub fn record_count(&self, count: u64) {
+        self.counter.fetch_add(count, Ordering::Relaxed);
+    }
+}
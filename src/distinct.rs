@@ -0,0 +1,77 @@
+//! Approximate distinct counting with a concurrent HyperLogLog sketch.
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+/// The number of bits of the hash used to pick a register, giving a standard error of ~0.8%.
+const PRECISION: u32 = 14;
+const REGISTERS: usize = 1 << PRECISION;
+
+/// A HyperLogLog sketch whose registers are updated lock-free with `fetch_max`.
+pub(crate) struct HyperLogLog {
+    registers: Box<[AtomicU8]>,
+}
+
+impl HyperLogLog {
+    pub(crate) fn new() -> Self {
+        Self {
+            registers: (0..REGISTERS).map(|_| AtomicU8::new(0)).collect(),
+        }
+    }
+
+    pub(crate) fn insert<K: Hash + ?Sized>(&self, key: &K) {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let hash = hasher.finish();
+        let index = (hash >> (64 - PRECISION)) as usize;
+        // Set a sentinel bit so the rank is bounded when the remaining bits are all zero.
+        let rest = (hash << PRECISION) | (1 << (PRECISION - 1));
+        let rank = rest.leading_zeros() as u8 + 1;
+        self.registers[index].fetch_max(rank, Ordering::Relaxed);
+    }
+
+    /// The estimated number of distinct keys inserted so far.
+    pub(crate) fn estimate(&self) -> u64 {
+        let m = REGISTERS as f64;
+        let mut sum = 0.0;
+        let mut zeros = 0;
+        for register in self.registers.iter() {
+            let rank = register.load(Ordering::Relaxed);
+            sum += 1.0 / (1_u64 << rank) as f64;
+            if rank == 0 {
+                zeros += 1;
+            }
+        }
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let estimate = alpha * m * m / sum;
+        if estimate <= 2.5 * m && zeros > 0 {
+            // Linear counting is more accurate for small cardinalities.
+            (m * (m / zeros as f64).ln()).round() as u64
+        } else {
+            estimate.round() as u64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate() {
+        let sketch = HyperLogLog::new();
+        assert_eq!(sketch.estimate(), 0);
+        for _ in 0..3 {
+            for key in 0..100 {
+                sketch.insert(&key);
+            }
+        }
+        assert_eq!(sketch.estimate(), 100);
+        for key in 0..200_000_u64 {
+            sketch.insert(&key);
+        }
+        let error = (sketch.estimate() as f64 - 200_000.0).abs() / 200_000.0;
+        assert!(error < 0.03, "{}", sketch.estimate());
+    }
+}
@@ -0,0 +1,144 @@
+//! Overrides of the builder options from environment variables, see [`ProgLogBuilder::from_env`].
+#[cfg(feature = "pretty_counts")]
+use crate::CountFormatterKind;
+use crate::ProgLogBuilder;
+use log::{warn, Level};
+use std::{env, str::FromStr, time::Duration};
+
+/// Set to a true value (`1`, `true`, `yes` or `on`) to mute every logger built with [`ProgLogBuilder::from_env`].
+pub(crate) const DISABLE_VAR: &str = "PROGLOG_DISABLE";
+
+/// Apply the environment overrides for the logger named by `builder`.
+///
+/// Invalid values are logged at [`Level::Warn`] and ignored.
+pub(crate) fn apply(builder: &mut ProgLogBuilder) {
+    let prefix = var_prefix(&builder.name);
+    if let Some(unit) = read(&builder.name, &format!("{}_UNIT", prefix), parse_unit) {
+        builder.unit = unit;
+    }
+    if let Some(level) = read(&builder.name, &format!("{}_LEVEL", prefix), |value| {
+        Level::from_str(value).map_err(|_| "expected one of error, warn, info, debug or trace")
+    }) {
+        builder.level = level;
+    }
+    if let Some(interval) = read(&builder.name, &format!("{}_INTERVAL", prefix), |value| {
+        parse_duration(value).ok_or("expected a duration, i.e. `30s`, `5m` or `1h`")
+    }) {
+        builder.target_interval = Some(interval);
+    }
+    #[cfg(feature = "pretty_counts")]
+    if let Some(formatter) = read(&builder.name, &format!("{}_FORMAT", prefix), parse_format) {
+        builder.count_formatter = formatter;
+    }
+    #[cfg(not(feature = "pretty_counts"))]
+    read(&builder.name, &format!("{}_FORMAT", prefix), |_| {
+        Err::<(), _>("requires the `pretty_counts` feature")
+    });
+    // Only ever mute, a false value doesn't unmute a logger muted in code or on the command line.
+    if let Some(true) = read(&builder.name, DISABLE_VAR, |value| {
+        parse_bool(value).ok_or("expected one of 1, true, yes, on, 0, false, no or off")
+    }) {
+        builder.muted = true;
+    }
+}
+
+/// The prefix of the variables for the logger `name`, i.e. `PROGLOG_READ_PAIRS` for `read-pairs`.
+fn var_prefix(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("PROGLOG_{}", name)
+}
+
+/// Read and parse the variable `var`, warning if it is set but can't be parsed.
+fn read<T>(
+    name: &str,
+    var: &str,
+    parse: impl FnOnce(&str) -> Result<T, &'static str>,
+) -> Option<T> {
+    let value = env::var(var).ok()?;
+    match parse(value.trim()) {
+        Ok(parsed) => Some(parsed),
+        Err(reason) => {
+            warn!("[{}] Ignoring {}={:?}: {}", name, var, value, reason);
+            None
+        }
+    }
+}
+
+fn parse_unit(value: &str) -> Result<u64, &'static str> {
+    match value.replace('_', "").parse() {
+        Ok(0) | Err(_) => Err("expected a positive integer"),
+        Ok(unit) => Ok(unit),
+    }
+}
+
+/// Parse a duration such as `250ms`, `1.5s`, `5m` or `1h`, a bare number is in seconds.
+pub(crate) fn parse_duration(value: &str) -> Option<Duration> {
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let (number, suffix) = value.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let secs = match suffix.trim() {
+        "ms" => number / 1e3,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return None,
+    };
+    Duration::try_from_secs_f64(secs)
+        .ok()
+        .filter(|duration| !duration.is_zero())
+}
+
+#[cfg(feature = "pretty_counts")]
+fn parse_format(value: &str) -> Result<CountFormatterKind, &'static str> {
+    match value.to_ascii_lowercase().as_str() {
+        "comma" => Ok(CountFormatterKind::Comma),
+        "dot" => Ok(CountFormatterKind::Dot),
+        "hexfour" => Ok(CountFormatterKind::HexFour),
+        "space" => Ok(CountFormatterKind::Space),
+        "underscore" => Ok(CountFormatterKind::Underscore),
+        "nothing" => Ok(CountFormatterKind::Nothing),
+        _ => Err("expected one of comma, dot, hexfour, space, underscore or nothing"),
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "" | "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(var_prefix("read-pairs 2"), "PROGLOG_READ_PAIRS_2");
+        assert_eq!(parse_unit("1_000"), Ok(1_000));
+        assert!(parse_unit("0").is_err());
+        assert!(parse_unit("-5").is_err());
+        assert_eq!(parse_duration("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_duration("1.5"), Some(Duration::from_millis(1_500)));
+        assert_eq!(parse_duration("5m"), Some(Duration::from_secs(300)));
+        assert_eq!(parse_duration("1h"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("0s"), None);
+        assert_eq!(parse_duration("soon"), None);
+        assert_eq!(parse_duration("5d"), None);
+        assert_eq!(parse_bool("Yes"), Some(true));
+        assert_eq!(parse_bool("off"), Some(false));
+        assert_eq!(parse_bool("maybe"), None);
+    }
+}
@@ -0,0 +1,246 @@
+//! A progress logger for values that go up and down, i.e. queue sizes or open connections.
+#[cfg(feature = "pretty_counts")]
+use crate::CountFormatterKind;
+use crate::{ticker::Ticker, DEFAULT_LEVEL, DEFAULT_NAME, DEFAULT_NOUN};
+use log::{log, Level};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+static DEFAULT_GAUGE_VERB: &str = "Holding";
+
+/// A thread-safe gauge that logs its value as `[{name}] {verb} {value} {noun}`.
+///
+/// Unlike [`ProgLog`](crate::ProgLog), which only counts up and emits a message every `unit`,
+/// a gauge can go up and down and emits a message when its value crosses one of the
+/// [`GaugeBuilder::threshold`]s, in either direction, and every [`GaugeBuilder::interval`].
+/// A final message is emitted when the gauge is dropped.
+///
+/// ```rust
+/// use proglog::GaugeBuilder;
+///
+/// let queue = GaugeBuilder::new().name("queue").noun("batches").threshold(100).build();
+/// queue.inc();
+/// queue.dec();
+/// ```
+pub struct Gauge {
+    /// The state of the gauge, shared with the timer thread.
+    inner: Arc<GaugeInner>,
+    /// Emits a message on a timer, if enabled, stopped on drop.
+    ticker: Option<Ticker>,
+}
+
+/// The state of a [`Gauge`].
+struct GaugeInner {
+    name: String,
+    noun: String,
+    verb: String,
+    level: Level,
+    #[cfg(feature = "pretty_counts")]
+    count_formatter: CountFormatterKind,
+    value: AtomicU64,
+    /// Emit a message each time the value crosses one of these.
+    thresholds: Vec<u64>,
+}
+
+impl Gauge {
+    /// The current value.
+    pub fn value(&self) -> u64 {
+        self.inner.value.load(Ordering::Relaxed)
+    }
+
+    /// Increment the value by 1, returning the new value.
+    pub fn inc(&self) -> u64 {
+        self.add(1)
+    }
+
+    /// Decrement the value by 1, not going below zero, returning the new value.
+    pub fn dec(&self) -> u64 {
+        self.sub(1)
+    }
+
+    /// Increase the value by `n`, returning the new value.
+    pub fn add(&self, n: u64) -> u64 {
+        self.update(|value| value.saturating_add(n))
+    }
+
+    /// Decrease the value by `n`, not going below zero, returning the new value.
+    pub fn sub(&self, n: u64) -> u64 {
+        self.update(|value| value.saturating_sub(n))
+    }
+
+    /// Set the value, returning the new value.
+    pub fn set(&self, value: u64) -> u64 {
+        self.update(|_| value)
+    }
+
+    /// Force the output of a log message.
+    pub fn flush(&self) {
+        self.inner.log_it(self.value());
+    }
+
+    fn update(&self, f: impl Fn(u64) -> u64) -> u64 {
+        let prev = self
+            .inner
+            .value
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |value| Some(f(value)))
+            .unwrap_or_else(|value| value);
+        let value = f(prev);
+        if self.inner.crossed(prev, value) {
+            self.inner.log_it(value);
+        }
+        value
+    }
+}
+
+impl Drop for Gauge {
+    /// Stop the timer and emit a final message.
+    fn drop(&mut self) {
+        self.ticker = None;
+        self.flush();
+    }
+}
+
+impl GaugeInner {
+    /// Whether going from `prev` to `value` crosses a threshold.
+    fn crossed(&self, prev: u64, value: u64) -> bool {
+        self.thresholds
+            .iter()
+            .any(|&threshold| (prev < threshold) != (value < threshold))
+    }
+
+    fn log_it(&self, value: u64) {
+        log!(
+            self.level,
+            "[{}] {} {} {}",
+            self.name,
+            self.verb,
+            self.fmt_count(value),
+            self.noun
+        );
+    }
+
+    /// Format a count with the configured formatter.
+    #[cfg(feature = "pretty_counts")]
+    fn fmt_count(&self, count: u64) -> String {
+        self.count_formatter.fmt(count)
+    }
+
+    /// Format a count with the configured formatter.
+    #[cfg(not(feature = "pretty_counts"))]
+    fn fmt_count(&self, count: u64) -> u64 {
+        count
+    }
+}
+
+/// The builder for [`Gauge`].
+pub struct GaugeBuilder {
+    name: String,
+    noun: String,
+    verb: String,
+    level: Level,
+    #[cfg(feature = "pretty_counts")]
+    count_formatter: CountFormatterKind,
+    initial: u64,
+    thresholds: Vec<u64>,
+    interval: Option<Duration>,
+}
+
+impl GaugeBuilder {
+    /// Create a new [`GaugeBuilder`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The name of the gauge.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// The noun used in the log output string format, ideally lowercase and plural.
+    pub fn noun(mut self, noun: impl Into<String>) -> Self {
+        self.noun = noun.into();
+        self
+    }
+
+    /// The verb used in the log output string format, ideally capitalized.
+    pub fn verb(mut self, verb: impl Into<String>) -> Self {
+        self.verb = verb.into();
+        self
+    }
+
+    /// The [`log::Level`] at which to emit log messages.
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// The formatter to use for outputting the value.
+    #[cfg(feature = "pretty_counts")]
+    pub fn count_formatter(mut self, formatter: CountFormatterKind) -> Self {
+        self.count_formatter = formatter;
+        self
+    }
+
+    /// The initial value.
+    pub fn initial(mut self, value: u64) -> Self {
+        self.initial = value;
+        self
+    }
+
+    /// Emit a message each time the value reaches `threshold` from below, or drops below it.
+    pub fn threshold(mut self, threshold: u64) -> Self {
+        self.thresholds.push(threshold);
+        self
+    }
+
+    /// Emit a message every `interval`.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Build the [`Gauge`] instance.
+    pub fn build(self) -> Gauge {
+        let inner = Arc::new(GaugeInner {
+            name: self.name,
+            noun: self.noun,
+            verb: self.verb,
+            level: self.level,
+            #[cfg(feature = "pretty_counts")]
+            count_formatter: self.count_formatter,
+            value: AtomicU64::new(self.initial),
+            thresholds: self.thresholds,
+        });
+        let ticker = self.interval.map(|interval| {
+            let inner = Arc::clone(&inner);
+            Ticker::spawn(
+                format!("proglog-gauge-{}", inner.name),
+                interval,
+                move || inner.log_it(inner.value.load(Ordering::Relaxed)),
+            )
+        });
+        Gauge { inner, ticker }
+    }
+}
+
+impl Default for GaugeBuilder {
+    fn default() -> Self {
+        Self {
+            name: String::from(DEFAULT_NAME),
+            noun: String::from(DEFAULT_NOUN),
+            verb: String::from(DEFAULT_GAUGE_VERB),
+            level: DEFAULT_LEVEL,
+            #[cfg(feature = "pretty_counts")]
+            count_formatter: CountFormatterKind::Nothing,
+            initial: 0,
+            thresholds: Vec::new(),
+            interval: None,
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Lock-free per-item latency histograms.
+use crate::ProgLog;
+use std::{
+    fmt::{self, Display},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+/// The number of linear sub-buckets per power of two, giving a relative error of at most 1/16.
+const SUB_BUCKET_BITS: u32 = 4;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+/// Enough buckets to cover every `u64` number of nanoseconds.
+const BUCKETS: usize = (64 - SUB_BUCKET_BITS as usize + 1) * SUB_BUCKETS;
+
+/// Percentiles of the per-item durations recorded with [`ProgLog::time`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LatencyStats {
+    /// The number of durations recorded.
+    pub count: u64,
+    /// The median duration.
+    pub p50: Duration,
+    /// The 95th percentile duration.
+    pub p95: Duration,
+    /// The 99th percentile duration.
+    pub p99: Duration,
+}
+
+impl LatencyStats {
+    /// Compute the percentiles from histogram bucket counts, `None` if the histogram is empty.
+    fn from_counts(counts: &[u64]) -> Option<Self> {
+        let count: u64 = counts.iter().sum();
+        if count == 0 {
+            return None;
+        }
+        let percentile = |q: f64| {
+            let rank = ((q * count as f64).ceil() as u64).max(1);
+            let mut seen = 0;
+            for (index, bucket) in counts.iter().enumerate() {
+                seen += bucket;
+                if seen >= rank {
+                    return Duration::from_nanos(bucket_value(index));
+                }
+            }
+            Duration::from_nanos(bucket_value(counts.len() - 1))
+        };
+        Some(Self {
+            count,
+            p50: percentile(0.50),
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+        })
+    }
+}
+
+impl Display for LatencyStats {
+    /// Formats as `p50 {p50} p95 {p95} p99 {p99}`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "p50 {} p95 {} p99 {}",
+            fmt_latency(self.p50),
+            fmt_latency(self.p95),
+            fmt_latency(self.p99)
+        )
+    }
+}
+
+/// Format a duration with a unit suited to its size, i.e. `850ns`, `12.3µs`, `4.5ms` or `1.25s`.
+fn fmt_latency(duration: Duration) -> String {
+    let nanos = duration.as_nanos();
+    if nanos < 1_000 {
+        format!("{}ns", nanos)
+    } else if nanos < 1_000_000 {
+        format!("{:.1}µs", nanos as f64 / 1e3)
+    } else if nanos < 1_000_000_000 {
+        format!("{:.1}ms", nanos as f64 / 1e6)
+    } else {
+        format!("{:.2}s", duration.as_secs_f64())
+    }
+}
+
+/// The bucket holding `nanos`.
+///
+/// Values below [`SUB_BUCKETS`] get their own bucket, above that each power of two is split
+/// into [`SUB_BUCKETS`] linear sub-buckets.
+fn bucket_index(nanos: u64) -> usize {
+    if nanos < SUB_BUCKETS as u64 {
+        return nanos as usize;
+    }
+    let shift = 63 - nanos.leading_zeros() - SUB_BUCKET_BITS;
+    let sub_bucket = (nanos >> shift) as usize - SUB_BUCKETS;
+    (shift as usize + 1) * SUB_BUCKETS + sub_bucket
+}
+
+/// The midpoint of the values held by the bucket at `index`.
+fn bucket_value(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64;
+    }
+    let shift = (index / SUB_BUCKETS - 1) as u32;
+    let sub_bucket = (index % SUB_BUCKETS) as u64;
+    let lower = (SUB_BUCKETS as u64 + sub_bucket) << shift;
+    lower + ((1 << shift) >> 1)
+}
+
+/// A lock-free, log-bucketed histogram of durations.
+pub(crate) struct Histogram {
+    buckets: Box<[AtomicU64]>,
+}
+
+impl Histogram {
+    pub(crate) fn new() -> Self {
+        Self {
+            buckets: (0..BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    pub(crate) fn record(&self, duration: Duration) {
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        self.buckets[bucket_index(nanos)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The percentiles of the durations recorded so far.
+    pub(crate) fn stats(&self) -> Option<LatencyStats> {
+        let counts: Vec<u64> = self
+            .buckets
+            .iter()
+            .map(|bucket| bucket.load(Ordering::Relaxed))
+            .collect();
+        LatencyStats::from_counts(&counts)
+    }
+
+    /// The percentiles of the durations recorded so far, resetting the histogram.
+    pub(crate) fn take(&self) -> Option<LatencyStats> {
+        let counts: Vec<u64> = self
+            .buckets
+            .iter()
+            .map(|bucket| bucket.swap(0, Ordering::Relaxed))
+            .collect();
+        LatencyStats::from_counts(&counts)
+    }
+}
+
+/// A guard returned by [`ProgLog::start_item`] that records the time until it is dropped.
+#[must_use = "the item is timed until the guard is dropped"]
+pub struct ItemTimer<'a> {
+    log: &'a ProgLog,
+    start: Instant,
+}
+
+impl<'a> ItemTimer<'a> {
+    pub(crate) fn new(log: &'a ProgLog) -> Self {
+        Self {
+            log,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for ItemTimer<'_> {
+    /// Record the elapsed time and count the item.
+    fn drop(&mut self) {
+        self.log.record_duration(self.start.elapsed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets() {
+        for nanos in [0, 1, 15, 16, 17, 31, 32, 1_000, 123_456_789, u64::MAX] {
+            let index = bucket_index(nanos);
+            assert!(index < BUCKETS);
+            let value = bucket_value(index);
+            let error = (value as f64 - nanos as f64).abs() / (nanos as f64).max(1.0);
+            assert!(error <= 1.0 / SUB_BUCKETS as f64, "{} -> {}", nanos, value);
+        }
+        let histogram = Histogram::new();
+        for micros in 1..=100 {
+            histogram.record(Duration::from_micros(micros));
+        }
+        let stats = histogram.take().unwrap();
+        assert_eq!(stats.count, 100);
+        assert!(stats.p50.abs_diff(Duration::from_micros(50)) <= Duration::from_micros(3));
+        assert!(stats.p99.abs_diff(Duration::from_micros(99)) <= Duration::from_micros(6));
+        assert!(histogram.stats().is_none());
+    }
+}
@@ -43,15 +43,33 @@
 //! The `pretty_counts` features turns on the ability to format the numbers in the log messages.
 //! Set the [`ProgLogBuilder::count_formatter`] to one of the [`CountFormatterKind`]s and numbers will
 //! be formatted accordingly. i.e. `100000000` -> `100_000_000` with [`CountFormatterKind::Underscore`].
+//!
+//! ## `serde`
+//!
+//! The `serde` feature derives `serde::Serialize` for [`ProgressSnapshot`], and adds
+//! `ProgLogConfig` and `ProgLogConfigs` for configuring loggers from i.e. a TOML or YAML file.
+//!
+//! ## `clap`
+//!
+//! The `clap` feature adds `ProgressArgs`, a set of `--progress-*` arguments to flatten into a
+//! `clap` command line and apply to a [`ProgLogBuilder`].
+//!
+//! ## `signal`
+//!
+//! The `signal` feature adds `dump_on_signal` on unix, which logs a snapshot of every registered
+//! logger whenever the process receives `SIGUSR1`.
 //! ```
 #![deny(missing_docs, unsafe_code)]
-use log::{log, Level};
+use log::{log, warn, Level};
 use std::{
-    fmt::Display,
+    fmt::{self, Display},
+    hash::Hash,
+    path::PathBuf,
     sync::{
-        atomic::{AtomicU64, Ordering},
-        Arc,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard, OnceLock, PoisonError,
     },
+    time::{Duration, Instant},
 };
 #[cfg(feature = "pretty_counts")]
 use thousands::{
@@ -64,15 +82,70 @@ static DEFAULT_NOUN: &str = "records";
 static DEFAULT_VERB: &str = "Processed";
 static DEFAULT_UNIT: u64 = 100_000;
 static DEFAULT_LEVEL: Level = Level::Info;
+static DEFAULT_RATE_HALF_LIFE: Duration = Duration::from_secs(60);
+static DEFAULT_SAMPLE_ERRORS: usize = 5;
+static DEFAULT_VALUE_NAME: &str = "value";
+static DEFAULT_CHECKPOINT_PERIOD: Duration = Duration::from_secs(60);
+/// The shortest period of the background threads, so that a zero period doesn't make them spin.
+static MIN_PERIOD: Duration = Duration::from_millis(1);
+
+mod aggregate;
+pub mod channel;
+mod checkpoint;
+mod children;
+#[cfg(feature = "clap")]
+mod cli;
+#[cfg(feature = "serde")]
+mod config;
+mod counter;
+mod distinct;
+mod env;
+mod gauge;
+mod latency;
+mod pipeline;
+mod registry;
+#[cfg(all(unix, feature = "signal"))]
+mod signal;
+mod snapshot;
+mod ticker;
+mod top_keys;
+use aggregate::Aggregate;
+pub use aggregate::ValueStats;
+use children::Children;
+#[cfg(feature = "clap")]
+pub use cli::ProgressArgs;
+#[cfg(feature = "serde")]
+pub use config::{ProgLogConfig, ProgLogConfigs, TierConfig};
+pub use counter::Counter;
+use distinct::HyperLogLog;
+pub use gauge::{Gauge, GaugeBuilder};
+use latency::Histogram;
+pub use latency::{ItemTimer, LatencyStats};
+pub use pipeline::{Pipeline, PipelineBuilder};
+pub use registry::{get, get_or_init, loggers, remove};
+#[cfg(all(unix, feature = "signal"))]
+pub use signal::dump_on_signal;
+pub use snapshot::ProgressSnapshot;
+use ticker::Ticker;
+pub use top_keys::TopKeys;
+use top_keys::TopKeysTracker;
 
 /// The types of formatting separators that can be applied to counts.
 #[cfg(feature = "pretty_counts")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
 pub enum CountFormatterKind {
     /// Delimit counter with a `,`.
     Comma,
     /// Delimit counter with a `.`.
     Dot,
     /// Delimit counter with a ` ` every four hexadecimal digits.
+    #[cfg_attr(feature = "clap", value(name = "hexfour"))]
     HexFour,
     /// Delimit counter with a ` `.
     Space,
@@ -119,14 +192,33 @@ impl CountFormatterKind {
 /// where `meta` is anything returned by the closure given to [`ProgLog::record_with`].
 /// `seen` is the number of items counted so far.
 ///
+/// Additional named counters, i.e. for categorized outcomes, can be added with [`ProgLogBuilder::counter`]
+/// and are reported after the main count:
+///
+/// ```text
+/// [{name}] {verb} {seen} {noun} ({counter} {counter_seen}, ...): {meta}
+/// ```
+///
+/// The current state of the logger can be inspected at any time with [`ProgLog::snapshot`].
+///
 /// A log message can be force-written by calling [`ProgLog::flush`]/[`ProgLog::flush_with`].
 /// Calling flush does not end the logger, another log message will be written on drop.
 /// Additionally, flush will be called on drop.
 ///
 /// **Note**: `unit` should be adjusted so that you emit ~1 log message every 15 seconds.
 /// If `unit` is too small and this is in a hot-loop logging will happen too frequently
-/// and impact performance.
+/// and impact performance. [`ProgLogBuilder::target_interval`] can adjust `unit` automatically.
 pub struct ProgLog {
+    /// The state of the logger, shared with any background threads.
+    inner: Arc<Inner>,
+    /// Background threads, i.e. the stall watchdog and heartbeat, stopped on drop.
+    tickers: Vec<Ticker>,
+    /// The logger this is a child of, see [`ProgLog::child`].
+    parent: Option<Arc<Inner>>,
+}
+
+/// The state of a [`ProgLog`].
+struct Inner {
     /// The counter tracks the number of items seen by the logger.
     counter: Arc<AtomicU64>,
     /// The name of the logger, used so that multiple progress loggers can run at once.
@@ -136,27 +228,160 @@ pub struct ProgLog {
     /// The verb used in the log output string format, ideally capitalized.
     verb: String,
     /// How many items must be seen before emitting a log message.
-    unit: u64,
-    /// The [`log::Level`] at which to emit log messages.
-    level: Level,
+    ///
+    /// This is atomic so that it can be adjusted on the fly, see [`ProgLogBuilder::target_interval`].
+    unit: AtomicU64,
+    /// The [`log::Level`] at which to emit log messages, stored as `Level as usize`.
+    ///
+    /// This is atomic so that it can be adjusted on the fly, see [`ProgLog::set_level`].
+    level: AtomicUsize,
+    /// Cleared to stop emitting messages, see [`ProgLog::mute`].
+    enabled: AtomicBool,
+    /// Additional `(unit, level)` cadences, see [`ProgLogBuilder::tier`].
+    tiers: Vec<(u64, Level)>,
     /// The formatter to use for outputting the current count.
     #[cfg(feature = "pretty_counts")]
     count_formatter: CountFormatterKind,
+    /// The expected total number of items, if known.
+    total: Option<u64>,
+    /// When the logger was created, used for computing rates.
+    start: Instant,
+    /// The number of log messages emitted so far.
+    messages: AtomicU64,
+    /// Rate bookkeeping that is updated each time a message is emitted.
+    emissions: Mutex<Emissions>,
+    /// The half-life of the exponentially weighted moving-average rate.
+    rate_half_life: Duration,
+    /// Additional named counters reported alongside the main count.
+    counters: Vec<Counter>,
+    /// The number of results seen by [`ProgLog::record_result`].
+    results: AtomicU64,
+    /// The number of errors seen by [`ProgLog::record_result`].
+    errors: AtomicU64,
+    /// The record number and message of the first `max_sample_errors` errors.
+    sample_errors: Mutex<Vec<(u64, String)>>,
+    /// How many error messages to keep for the final summary.
+    max_sample_errors: usize,
+    /// The name of the values given to [`ProgLog::record_value`].
+    value_name: String,
+    /// Aggregate of all values given to [`ProgLog::record_value`].
+    values: Aggregate,
+    /// Aggregate of the values given to [`ProgLog::record_value`] since the last message.
+    interval_values: Aggregate,
+    /// Histogram of all durations given to [`ProgLog::record_duration`], allocated on first use.
+    latencies: OnceLock<Histogram>,
+    /// Histogram of the durations given to [`ProgLog::record_duration`] since the last message.
+    interval_latencies: OnceLock<Histogram>,
+    /// The number of keys given to [`ProgLog::record_key`].
+    keys: AtomicU64,
+    /// Sketch of the distinct keys given to [`ProgLog::record_key`], allocated on first use.
+    distinct_keys: OnceLock<HyperLogLog>,
+    /// Tracker of the most frequent keys given to [`ProgLog::record_keyed`], if enabled.
+    top_keys: Option<TopKeysTracker>,
+    /// Report the top keys in every `top_keys_every` messages, `0` for only in the summary.
+    top_keys_every: u64,
+    /// Adjust `unit` at each message so that messages are emitted about this often.
+    target_interval: Option<Duration>,
+    /// Escalate messages to [`Level::Warn`] when the recent rate is below this rate.
+    min_rate: Option<f64>,
+    /// Escalate messages to [`Level::Warn`] when the recent rate is below this fraction of the peak rate.
+    min_rate_of_peak: Option<f64>,
+    /// The noun used by child loggers, see [`ProgLog::child`].
+    child_noun: String,
+    /// The unit used by child loggers.
+    child_unit: u64,
+    /// The counts of the child loggers, allocated when the first child is created.
+    children: OnceLock<Children>,
+    /// The sum of the weights given to [`ProgLog::record_weighted`], stored as `f64` bits.
+    weight: AtomicU64,
+    /// The expected total weight, used for the percent complete and ETA instead of `total`.
+    weighted_total: Option<f64>,
+    /// The count the logger started at, see [`ProgLogBuilder::initial_count`].
+    ///
+    /// Rates only count the items seen since then, so that they reflect the current process.
+    initial_count: u64,
+    /// Where to save the count, see [`ProgLogBuilder::checkpoint`].
+    checkpoint: Option<PathBuf>,
+    /// Held while writing the checkpoint file.
+    checkpoint_lock: Mutex<()>,
+    /// The counters of the loggers counting the items sent and received through a channel, see [`channel`].
+    in_flight: OnceLock<(Arc<AtomicU64>, Arc<AtomicU64>)>,
+    /// Called after each message, i.e. by a [`Pipeline`] to emit its combined message.
+    on_message: OnceLock<Box<dyn Fn() + Send + Sync>>,
+}
+
+/// Stats collected since the previous message, reset each time a message is emitted.
+struct Interval {
+    values: ValueStats,
+    latency: Option<LatencyStats>,
+    /// The top keys, if they are due to be reported in this message.
+    top_keys: Option<TopKeys>,
+    /// Set if the recent rate is below the expected rate.
+    slowdown: Option<Slowdown>,
+}
+
+impl Interval {
+    /// The level to emit the message at, escalated to [`Level::Warn`] on a slowdown.
+    fn level(&self, level: Level) -> Level {
+        if self.slowdown.is_some() {
+            level.min(Level::Warn)
+        } else {
+            level
+        }
+    }
+}
+
+/// Why a message was escalated to [`Level::Warn`], see [`ProgLogBuilder::min_rate`].
+enum Slowdown {
+    /// The recent rate is below the expected minimum rate.
+    BelowMinimum { rate: f64, min_rate: f64 },
+    /// The recent rate is below a fraction of the peak rate.
+    BelowPeak { rate: f64, peak_rate: f64 },
+}
+
+/// Bookkeeping for the most recently emitted log message.
+///
+/// This is only touched when a message is emitted, so the lock is kept off of the hot path.
+struct Emissions {
+    /// When the last message that advanced the count was emitted.
+    at: Instant,
+    /// The count at the last message.
+    count: u64,
+    /// When the last message was emitted, whether or not the count advanced.
+    last_message: Instant,
+    /// The rate in items per second between the last two messages.
+    recent_rate: Option<f64>,
+    /// The exponentially weighted moving-average rate in items per second.
+    ewma_rate: Option<f64>,
+    /// The highest moving-average rate seen so far.
+    peak_rate: Option<f64>,
+}
+
+impl Emissions {
+    fn new(at: Instant) -> Self {
+        Self {
+            at,
+            count: 0,
+            last_message: at,
+            recent_rate: None,
+            ewma_rate: None,
+            peak_rate: None,
+        }
+    }
 }
 
 impl Default for ProgLog {
     /// Default for [`ProgLog`].
     fn default() -> Self {
-        Self {
-            counter: Default::default(),
-            name: String::from(DEFAULT_NAME),
-            noun: String::from(DEFAULT_NOUN),
-            verb: String::from(DEFAULT_VERB),
-            unit: DEFAULT_UNIT,
-            level: DEFAULT_LEVEL,
+        Self::new(
+            String::from(DEFAULT_NAME),
+            String::from(DEFAULT_NOUN),
+            String::from(DEFAULT_VERB),
+            DEFAULT_UNIT,
+            DEFAULT_LEVEL,
             #[cfg(feature = "pretty_counts")]
-            count_formatter: CountFormatterKind::Nothing,
-        }
+            CountFormatterKind::Nothing,
+        )
     }
 }
 
@@ -173,8 +398,7 @@ impl ProgLog {
         level: Level,
         #[cfg(feature = "pretty_counts")] count_formatter: CountFormatterKind,
     ) -> Self {
-        Self {
-            counter: Arc::new(AtomicU64::new(0)),
+        Self::from_inner(Inner::new(
             name,
             noun,
             verb,
@@ -182,6 +406,14 @@ impl ProgLog {
             level,
             #[cfg(feature = "pretty_counts")]
             count_formatter,
+        ))
+    }
+
+    fn from_inner(inner: Inner) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            tickers: Vec::new(),
+            parent: None,
         }
     }
 
@@ -190,90 +422,220 @@ impl ProgLog {
     /// This should be treated with some caution as it is using the
     /// atomic load with [`Ordering::Relaxed`].
     pub fn seen(&self) -> u64 {
-        self.counter.load(Ordering::Relaxed)
+        self.inner.counter.load(Ordering::Relaxed)
     }
 
-    /// Helper method to pull out log formatting .
-    #[inline]
-    fn log_it(&self, total: u64) {
-        #[cfg(feature = "pretty_counts")]
-        {
-            log!(
-                self.level,
-                "[{name}] {verb} {seen} {noun}",
-                name = &self.name,
-                verb = &self.verb,
-                seen = self.count_formatter.fmt(total),
-                noun = &self.noun
-            );
-        }
-        #[cfg(not(feature = "pretty_counts"))]
-        {
-            log!(
-                self.level,
-                "[{name}] {verb} {seen} {noun}",
-                name = &self.name,
-                verb = &self.verb,
-                seen = total,
-                noun = &self.noun
-            );
+    /// Take a snapshot of the current progress.
+    ///
+    /// Rates are in items per second. The percent complete and ETA are only available
+    /// when a total was given via [`ProgLogBuilder::total`]. The ETA is based on the
+    /// moving-average rate once a message has been emitted, and the lifetime rate before that.
+    pub fn snapshot(&self) -> ProgressSnapshot {
+        let inner = &self.inner;
+        let count = inner.count();
+        let elapsed = inner.start.elapsed();
+        let rate = rate(count.saturating_sub(inner.initial_count), elapsed);
+        let (recent_rate, ewma_rate) = {
+            let emissions = inner.lock_emissions();
+            (emissions.recent_rate, emissions.ewma_rate)
+        };
+        let (percent, eta) = match inner.weighted_progress() {
+            Some((percent, eta)) => (Some(percent), eta),
+            None => (
+                inner
+                    .total
+                    .filter(|&total| total > 0)
+                    .map(|total| count as f64 / total as f64 * 100.0),
+                inner
+                    .total
+                    .and_then(|total| eta(total.saturating_sub(count), ewma_rate.unwrap_or(rate))),
+            ),
+        };
+        ProgressSnapshot {
+            unit: inner.unit(),
+            name: inner.name.clone(),
+            noun: inner.noun.clone(),
+            verb: inner.verb.clone(),
+            count,
+            total: inner.total,
+            elapsed,
+            rate,
+            recent_rate,
+            ewma_rate,
+            eta,
+            percent,
+            messages: inner.messages.load(Ordering::Relaxed),
+            errors: inner.errors.load(Ordering::Relaxed),
+            values: Some(inner.values.stats()).filter(|values| values.count > 0),
+            latency: inner.latencies.get().and_then(Histogram::stats),
+            distinct: inner.distinct(),
+            duplication: inner.duplication(),
+            top_keys: inner.top_keys.as_ref().map(TopKeysTracker::top),
+            weight: inner.weighted_total.map(|_| inner.weight()),
+            counters: inner
+                .counters
+                .iter()
+                .map(|counter| (counter.name().to_owned(), counter.seen()))
+                .collect(),
         }
     }
 
-    /// Helper method to pull out log formatting with custom user closure.
-    #[inline]
-    fn log_it_with<F, T>(&self, f: F, total: u64)
+    /// Get the additional named counter `name`, see [`ProgLogBuilder::counter`].
+    ///
+    /// Named counters are reported alongside the main count, but do not drive the emission of messages.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` was not registered with [`ProgLogBuilder::counter`].
+    pub fn counter(&self, name: &str) -> &Counter {
+        self.inner
+            .counters
+            .iter()
+            .find(|counter| counter.name() == name)
+            .unwrap_or_else(|| panic!("[{}] No counter named `{}`", self.inner.name, name))
+    }
+
+    /// Increment the progress logger by 1 and check if a new message should be emitted.
+    ///
+    /// Returns `true` if total seen after incrementing is a multiple of `unit`.
+    pub fn record(&self) -> bool {
+        self.inner.record()
+    }
+
+    /// Increment the progress logger by 1, counting the result as an error if it is an `Err`,
+    /// and check if a new message should be emitted.
+    ///
+    /// Once this has been called, each message reports the number of errors and the percent of
+    /// results that were errors. The first few error messages are kept, see [`ProgLogBuilder::sample_errors`],
+    /// and are logged in a summary when the logger is dropped.
+    ///
+    /// Returns `true` if total seen after incrementing is a multiple of `unit`.
+    pub fn record_result<T, E>(&self, result: &Result<T, E>) -> bool
     where
-        F: Fn() -> T,
-        T: Display,
+        E: Display,
     {
-        #[cfg(feature = "pretty_counts")]
-        {
-            log!(
-                self.level,
-                "[{name}] {verb} {seen} {noun}: {extra}",
-                name = &self.name,
-                verb = &self.verb,
-                seen = self.count_formatter.fmt(total),
-                noun = &self.noun,
-                extra = f()
-            );
+        let prev = self.inner.fetch_add(1);
+        let total = prev + 1;
+        self.inner.results.fetch_add(1, Ordering::Relaxed);
+        if let Err(err) = result {
+            let errors = self.inner.errors.fetch_add(1, Ordering::Relaxed) + 1;
+            if errors <= self.inner.max_sample_errors as u64 {
+                lock(&self.inner.sample_errors).push((total, err.to_string()));
+            }
         }
-
-        #[cfg(not(feature = "pretty_counts"))]
-        {
-            log!(
-                self.level,
-                "[{name}] {verb} {seen} {noun}: {extra}",
-                name = &self.name,
-                verb = &self.verb,
-                seen = total,
-                noun = &self.noun,
-                extra = f()
-            );
+        if let Some(level) = self.inner.boundary(total) {
+            self.inner.log_it(total, level);
+            true
+        } else {
+            false
         }
     }
 
-    fn count(&self) -> u64 {
-        self.counter.load(Ordering::Relaxed)
+    /// Increment the progress logger by 1, adding `value` to the aggregated values, and check if
+    /// a new message should be emitted.
+    ///
+    /// Once this has been called, each message reports the sum, mean, min and max of the values
+    /// seen over the lifetime of the logger and since the previous message.
+    /// See [`ProgLogBuilder::value_name`] for naming the values.
+    ///
+    /// Returns `true` if total seen after incrementing is a multiple of `unit`.
+    pub fn record_value(&self, value: u64) -> bool {
+        self.record_value_f64(value as f64)
     }
 
-    fn fetch_add(&self, count: u64) -> u64 {
-        self.counter.fetch_add(count, Ordering::Relaxed)
+    /// Increment the progress logger by 1, adding `value` to the aggregated values, and check if
+    /// a new message should be emitted.
+    ///
+    /// See [`ProgLog::record_value`].
+    pub fn record_value_f64(&self, value: f64) -> bool {
+        self.inner.values.add(value);
+        self.inner.interval_values.add(value);
+        self.record()
     }
 
-    /// Increment the progress logger by 1 and check if a new message should be emitted.
+    /// Increment the progress logger by 1, timing how long `f` takes, and check if a new message
+    /// should be emitted.
+    ///
+    /// Once an item has been timed, each message reports the p50/p95/p99 latency of the items
+    /// timed since the previous message, and the summary logged on drop reports them for the
+    /// lifetime of the logger.
+    pub fn time<R>(&self, f: impl FnOnce() -> R) -> R {
+        let _timer = self.start_item();
+        f()
+    }
+
+    /// Start timing an item, the item is recorded when the returned guard is dropped.
+    ///
+    /// See [`ProgLog::time`].
+    pub fn start_item(&self) -> ItemTimer<'_> {
+        ItemTimer::new(self)
+    }
+
+    /// Increment the progress logger by 1, adding `duration` to the latency histograms, and check
+    /// if a new message should be emitted.
+    ///
+    /// See [`ProgLog::time`].
     ///
     /// Returns `true` if total seen after incrementing is a multiple of `unit`.
-    pub fn record(&self) -> bool {
-        let prev: u64 = self.fetch_add(1);
-        let total = prev + 1;
-        if total % self.unit == 0 {
-            self.log_it(total);
-            true
-        } else {
-            false
+    pub fn record_duration(&self, duration: Duration) -> bool {
+        self.inner
+            .latencies
+            .get_or_init(Histogram::new)
+            .record(duration);
+        self.inner
+            .interval_latencies
+            .get_or_init(Histogram::new)
+            .record(duration);
+        self.record()
+    }
+
+    /// Increment the progress logger by 1, adding `key` to the distinct key sketch, and check if
+    /// a new message should be emitted.
+    ///
+    /// Once this has been called, each message reports the approximate number of distinct keys
+    /// seen so far and the fraction of keys that were duplicates. The estimate is from a
+    /// HyperLogLog sketch with a standard error of ~0.8%.
+    ///
+    /// Returns `true` if total seen after incrementing is a multiple of `unit`.
+    pub fn record_key<K: Hash + ?Sized>(&self, key: &K) -> bool {
+        self.inner
+            .distinct_keys
+            .get_or_init(HyperLogLog::new)
+            .insert(key);
+        self.inner.keys.fetch_add(1, Ordering::Relaxed);
+        self.record()
+    }
+
+    /// Increment the progress logger by 1, counting `key` towards the most frequent keys, and
+    /// check if a new message should be emitted.
+    ///
+    /// The top keys are only tracked if enabled with [`ProgLogBuilder::top_keys`], and are
+    /// reported in the summary logged on drop, and optionally in messages, see
+    /// [`ProgLogBuilder::top_keys_every`]. This is useful for spotting skew in the input, i.e.
+    /// one contig or barcode dominating.
+    ///
+    /// Returns `true` if total seen after incrementing is a multiple of `unit`.
+    pub fn record_keyed(&self, key: &str) -> bool {
+        if let Some(top_keys) = &self.inner.top_keys {
+            top_keys.record(key);
+        }
+        self.record()
+    }
+
+    /// Increment the progress logger by 1, adding `weight` to the completed weight, and check if a
+    /// new message should be emitted.
+    ///
+    /// This is for work made up of items of different cost, i.e. a few big files and many small
+    /// ones. With [`ProgLogBuilder::weighted_total`] set, the percent complete and ETA are computed
+    /// from the weight, and each message reports them after the item count. Negative and `NaN`
+    /// weights are ignored.
+    ///
+    /// Returns `true` if total seen after incrementing is a multiple of `unit`.
+    pub fn record_weighted(&self, weight: f64) -> bool {
+        if weight > 0.0 {
+            aggregate::update(&self.inner.weight, |sum| Some(sum + weight));
         }
+        self.record()
     }
 
     /// Increment the progress logger by `count`, checking if new message(s) should be emitted.
@@ -284,17 +646,20 @@ impl ProgLog {
     /// **Note**: Calling `record` 10 times with `unit` of 2 will emit 5 messages, but calling this with `count=5` twice will emit 4 messages.
     pub fn record_count(&self, mut count: u64) -> usize {
         let mut retval = 0;
-        while count > self.unit {
-            self.log_it(self.count() + self.unit);
+        let unit = self.inner.unit();
+        while count > unit {
+            self.inner
+                .log_it(self.inner.count() + unit, self.inner.level());
             retval += 1;
-            count -= self.unit;
-            self.fetch_add(self.unit);
+            count -= unit;
+            self.inner.fetch_add(unit);
         }
-        if (count + (self.count() % self.unit)) > self.unit {
-            self.log_it(self.count() + count);
+        if (count + (self.inner.count() % unit)) > unit {
+            self.inner
+                .log_it(self.inner.count() + count, self.inner.level());
             retval += 1;
         }
-        self.fetch_add(count);
+        self.inner.fetch_add(count);
         retval
     }
 
@@ -324,10 +689,10 @@ impl ProgLog {
         F: Fn() -> T,
         T: Display,
     {
-        let prev = self.fetch_add(1);
+        let prev = self.inner.fetch_add(1);
         let total = prev + 1;
-        if total % self.unit == 0 {
-            self.log_it_with(f, total);
+        if let Some(level) = self.inner.boundary(total) {
+            self.inner.log_it_with(f, total, level);
             true
         } else {
             false
@@ -347,15 +712,18 @@ impl ProgLog {
         T: Display,
     {
         let mut retval = 0usize;
-        while count > self.unit {
-            self.log_it_with(&f, self.count() + self.unit);
+        let unit = self.inner.unit();
+        while count > unit {
+            self.inner
+                .log_it_with(&f, self.inner.count() + unit, self.inner.level());
             retval += 1;
-            count -= self.unit;
-            self.fetch_add(self.unit);
+            count -= unit;
+            self.inner.fetch_add(unit);
         }
-        self.fetch_add(count);
-        if (count + (self.count() % self.unit)) > self.unit {
-            self.log_it_with(f, self.count() + count);
+        self.inner.fetch_add(count);
+        if (count + (self.inner.count() % unit)) > unit {
+            self.inner
+                .log_it_with(f, self.inner.count() + count, self.inner.level());
             retval += 1;
         }
         retval
@@ -370,9 +738,9 @@ impl ProgLog {
         F: Fn() -> T,
         T: Display,
     {
-        let total = self.count();
-        if total % self.unit != 0 {
-            self.log_it_with(f, total);
+        let total = self.inner.count();
+        if self.inner.boundary(total).is_none() {
+            self.inner.log_it_with(f, total, self.inner.level());
         }
     }
 
@@ -381,135 +749,1108 @@ impl ProgLog {
     /// This does not increment the counter.
     /// This does not close the logger.
     pub fn flush(&self) {
-        let total = self.count();
-        if total % self.unit != 0 {
-            self.log_it(total);
+        let total = self.inner.count();
+        if self.inner.boundary(total).is_none() {
+            self.inner.log_it(total, self.inner.level());
         }
     }
-}
 
-impl Drop for ProgLog {
-    /// Drop the logger, calling flush before dropping.
-    fn drop(&mut self) {
-        self.flush();
+    /// Create a child logger, i.e. counting the records within one of the files counted by this logger.
+    ///
+    /// The child is named `{name}/{child}`, counts [`ProgLogBuilder::child_noun`] every
+    /// [`ProgLogBuilder::child_unit`] items, and otherwise uses the verb, level and formatting of
+    /// this logger. When the child is dropped its final count is rolled into this logger, and one
+    /// item is recorded on this logger, unless this logger has already been dropped. Messages from
+    /// this logger then report the items seen by all of its children, and the
+    /// [`ProgLogBuilder::total`] if set, i.e.
+    /// `Processed 3/22 files; 1200000 records in progress; 27400000 records total`.
+    pub fn child(&self, name: impl Display) -> ProgLog {
+        let parent = &self.inner;
+        let inner = Inner::new(
+            format!("{}/{}", parent.name, name),
+            parent.child_noun.clone(),
+            parent.verb.clone(),
+            parent.child_unit,
+            parent.level(),
+            #[cfg(feature = "pretty_counts")]
+            parent.count_formatter,
+        );
+        parent
+            .children
+            .get_or_init(Children::new)
+            .add(Arc::clone(&inner.counter));
+        let mut child = ProgLog::from_inner(inner);
+        child.parent = Some(Arc::clone(parent));
+        child
     }
-}
-
-/// The builder for [`ProgLog`].
-pub struct ProgLogBuilder {
-    name: String,
-    noun: String,
-    verb: String,
-    unit: u64,
-    level: Level,
-    /// The formatter to use for outputting the current count.
-    #[cfg(feature = "pretty_counts")]
-    count_formatter: CountFormatterKind,
-}
 
-impl ProgLogBuilder {
-    /// Create a new [`ProgLogBuilder`].
-    pub fn new() -> Self {
-        Self::default()
+    /// The current number of items between messages.
+    pub fn unit(&self) -> u64 {
+        self.inner.unit()
     }
 
-    /// The name of the logger, used so that multiple progress loggers can run at once.
-    pub fn name(mut self, name: impl Into<String>) -> Self {
-        self.name = name.into();
-        self
+    /// Change how many items must be seen before emitting a log message.
+    ///
+    /// This takes effect immediately for all threads, the next message is emitted at the next
+    /// multiple of the new `unit`. A `unit` of `0` is treated as `1`. If
+    /// [`ProgLogBuilder::target_interval`] is set, the unit will be adjusted again at the next message.
+    pub fn set_unit(&self, unit: u64) {
+        self.inner.unit.store(unit.max(1), Ordering::Relaxed);
     }
 
-    /// The noun used in the log output string format, ideally lowercase and plural.
-    pub fn noun(mut self, noun: impl Into<String>) -> Self {
-        self.noun = noun.into();
-        self
+    /// The current [`log::Level`] at which messages are emitted.
+    pub fn level(&self) -> Level {
+        self.inner.level()
     }
 
-    /// The verb used in the log output string format, ideally capitalized.
-    pub fn verb(mut self, verb: impl Into<String>) -> Self {
-        self.verb = verb.into();
-        self
+    /// Change the [`log::Level`] at which messages are emitted.
+    ///
+    /// This only changes the primary level, tiers added with [`ProgLogBuilder::tier`] keep their own.
+    pub fn set_level(&self, level: Level) {
+        self.inner.level.store(level as usize, Ordering::Relaxed);
     }
 
-    /// How many items must be seen before emitting a log message.
-    pub fn unit(mut self, unit: u64) -> Self {
-        self.unit = unit;
-        self
+    /// Stop emitting messages, including from [`ProgLog::flush`], background threads and the
+    /// summary logged on drop.
+    ///
+    /// Items are still counted while muted, so the counts are correct once unmuted.
+    pub fn mute(&self) {
+        self.inner.enabled.store(false, Ordering::Relaxed);
     }
 
-    /// The [`log::Level`] at which to emit log messages.
-    pub fn level(mut self, level: Level) -> Self {
-        self.level = level;
-        self
+    /// Resume emitting messages after [`ProgLog::mute`].
+    pub fn unmute(&self) {
+        self.inner.enabled.store(true, Ordering::Relaxed);
     }
 
-    /// The formatter to use for outputting the current count.
-    #[cfg(feature = "pretty_counts")]
-    pub fn count_formatter(mut self, formatter: CountFormatterKind) -> Self {
-        self.count_formatter = formatter;
-        self
+    /// Whether the logger is muted, see [`ProgLog::mute`].
+    pub fn is_muted(&self) -> bool {
+        self.inner.is_muted()
     }
+}
 
-    /// Build the [`ProgLog`] instance.
-    pub fn build(self) -> ProgLog {
-        ProgLog::new(
-            self.name,
-            self.noun,
-            self.verb,
-            self.unit,
-            self.level,
-            #[cfg(feature = "pretty_counts")]
-            self.count_formatter,
-        )
-    }
+/// The body of a progress message: `[{name}] {verb} {seen} {noun}` followed by any named counters
+/// and other stats.
+///
+/// This is formatted lazily so that nothing is done when the log level is disabled.
+struct Progress<'a> {
+    log: &'a Inner,
+    total: u64,
+    interval: Interval,
 }
 
-impl Default for ProgLogBuilder {
-    fn default() -> Self {
-        Self {
-            name: String::from(DEFAULT_NAME),
-            noun: String::from(DEFAULT_NOUN),
-            verb: String::from(DEFAULT_VERB),
-            unit: DEFAULT_UNIT,
-            level: DEFAULT_LEVEL,
-            #[cfg(feature = "pretty_counts")]
-            count_formatter: CountFormatterKind::Nothing,
+impl Display for Progress<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let log = self.log;
+        write!(
+            f,
+            "[{}] {} {}",
+            log.name,
+            log.verb,
+            log.fmt_count(self.total)
+        )?;
+        if let (Some(total), Some(_)) = (log.total, log.children.get()) {
+            write!(f, "/{}", log.fmt_count(total))?;
+        }
+        write!(f, " {}", log.noun)?;
+        for (i, counter) in log.counters.iter().enumerate() {
+            let sep = if i == 0 { " (" } else { ", " };
+            write!(
+                f,
+                "{}{} {}",
+                sep,
+                counter.name(),
+                log.fmt_count(counter.seen())
+            )?;
+        }
+        if !log.counters.is_empty() {
+            write!(f, ")")?;
+        }
+        if let Some(children) = log.children.get() {
+            let (in_progress, total) = children.counts();
+            write!(
+                f,
+                "; {} {noun} in progress; {} {noun} total",
+                log.fmt_count(in_progress),
+                log.fmt_count(total),
+                noun = log.child_noun
+            )?;
+        }
+        if let Some(counters) = log.in_flight.get() {
+            write!(
+                f,
+                "; {} in flight",
+                log.fmt_count(channel::in_flight(counters))
+            )?;
+        }
+        if let Some((percent, eta)) = log.weighted_progress() {
+            write!(f, "; {:.1}% complete", percent)?;
+            if let Some(eta) = eta {
+                write!(f, ", ETA {}", snapshot::fmt_duration(eta))?;
+            }
+        }
+        let results = log.results.load(Ordering::Relaxed);
+        if results > 0 {
+            let errors = log.errors.load(Ordering::Relaxed);
+            write!(
+                f,
+                "; {} errors ({:.2}%)",
+                log.fmt_count(errors),
+                errors as f64 / results as f64 * 100.0
+            )?;
+        }
+        let values = log.values.stats();
+        if values.count > 0 {
+            write!(f, "; {} {}", log.value_name, values)?;
+            if self.interval.values.count > 0 {
+                write!(f, " (interval {})", self.interval.values)?;
+            }
+        }
+        if let Some(latency) = &self.interval.latency {
+            write!(f, "; latency {}", latency)?;
+        }
+        if let (Some(distinct), Some(duplication)) = (log.distinct(), log.duplication()) {
+            write!(
+                f,
+                "; ~{} distinct keys ({:.2}% duplicates)",
+                log.fmt_count(distinct),
+                duplication * 100.0
+            )?;
+        }
+        if let Some(top_keys) = &self.interval.top_keys {
+            write!(f, "; top keys {}", top_keys)?;
         }
+        match self.interval.slowdown {
+            Some(Slowdown::BelowMinimum { rate, min_rate }) => write!(
+                f,
+                "; slow: {:.1} {noun}/s is below the expected {:.1} {noun}/s",
+                rate,
+                min_rate,
+                noun = log.noun
+            )?,
+            Some(Slowdown::BelowPeak { rate, peak_rate }) => write!(
+                f,
+                "; slow: {:.1} {noun}/s is {:.0}% of the peak {:.1} {noun}/s",
+                rate,
+                rate / peak_rate * 100.0,
+                peak_rate,
+                noun = log.noun
+            )?,
+            None => (),
+        }
+        Ok(())
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use std::collections::HashSet;
+/// Lock a mutex, ignoring poisoning since the guarded state is only ever bookkeeping.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
 
-    use super::*;
-    use logtest::Logger;
-    use rayon::prelude::*;
+/// Items per second.
+fn rate(count: u64, elapsed: Duration) -> f64 {
+    let secs = elapsed.as_secs_f64();
+    if secs > 0.0 {
+        count as f64 / secs
+    } else {
+        0.0
+    }
+}
 
-    fn drain_logger(logger: &mut Logger) {
-        while let Some(_msg) = logger.pop() {}
-        assert_eq!(logger.len(), 0);
+/// Fold a `rate` observed over `elapsed` into the moving average `prev`.
+///
+/// Observations are weighted by how long they cover, so that an observation one `half_life`
+/// long counts for half of the new average regardless of how often messages are emitted.
+fn ewma(prev: Option<f64>, rate: f64, elapsed: Duration, half_life: Duration) -> f64 {
+    match prev {
+        None => rate,
+        Some(_) if half_life.is_zero() => rate,
+        Some(prev) => {
+            let alpha = 1.0 - 0.5_f64.powf(elapsed.as_secs_f64() / half_life.as_secs_f64());
+            alpha * rate + (1.0 - alpha) * prev
+        }
     }
+}
 
-    /// This function drives all other tests since the logtest Logger is global :(.
-    ///
-    /// Each function called is expected to drain the logger.
-    #[test]
-    fn test_log_messages() {
-        let mut logger = logtest::Logger::start();
-        assert_eq!(logger.len(), 0);
-        test_simple_case(&mut logger);
-        drain_logger(&mut logger);
+/// Round `unit` down to a "nice" number, 1, 2 or 5 × 10^k, so that messages land on readable counts.
+fn nice_unit(unit: f64) -> u64 {
+    if unit.is_nan() || unit < 1.0 {
+        return 1;
+    }
+    let mut magnitude = 10_f64.powi(unit.log10().floor() as i32);
+    // Guard against log10 landing just below an exact power of ten.
+    if unit / magnitude >= 10.0 {
+        magnitude *= 10.0;
+    }
+    let mantissa = unit / magnitude;
+    let nice = if mantissa >= 5.0 {
+        5.0
+    } else if mantissa >= 2.0 {
+        2.0
+    } else {
+        1.0
+    };
+    ((nice * magnitude).min(u64::MAX as f64) as u64).max(1)
+}
 
-        test_rayon(&mut logger);
-        drain_logger(&mut logger);
+/// Time to see `remaining` items at `rate` items per second, `None` if that is too long to represent.
+fn eta(remaining: u64, rate: f64) -> Option<Duration> {
+    if remaining == 0 {
+        Some(Duration::ZERO)
+    } else if rate > 0.0 {
+        Duration::try_from_secs_f64(remaining as f64 / rate).ok()
+    } else {
+        None
+    }
+}
+
+impl Inner {
+    fn new(
+        name: String,
+        noun: String,
+        verb: String,
+        unit: u64,
+        level: Level,
+        #[cfg(feature = "pretty_counts")] count_formatter: CountFormatterKind,
+    ) -> Self {
+        let start = Instant::now();
+        Self {
+            counter: Arc::new(AtomicU64::new(0)),
+            name,
+            noun,
+            verb,
+            unit: AtomicU64::new(unit),
+            level: AtomicUsize::new(level as usize),
+            enabled: AtomicBool::new(true),
+            tiers: Vec::new(),
+            #[cfg(feature = "pretty_counts")]
+            count_formatter,
+            total: None,
+            start,
+            messages: AtomicU64::new(0),
+            emissions: Mutex::new(Emissions::new(start)),
+            rate_half_life: DEFAULT_RATE_HALF_LIFE,
+            counters: Vec::new(),
+            results: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            sample_errors: Mutex::new(Vec::new()),
+            max_sample_errors: DEFAULT_SAMPLE_ERRORS,
+            value_name: String::from(DEFAULT_VALUE_NAME),
+            values: Aggregate::new(),
+            interval_values: Aggregate::new(),
+            latencies: OnceLock::new(),
+            interval_latencies: OnceLock::new(),
+            keys: AtomicU64::new(0),
+            distinct_keys: OnceLock::new(),
+            top_keys: None,
+            top_keys_every: 0,
+            target_interval: None,
+            min_rate: None,
+            min_rate_of_peak: None,
+            child_noun: String::from(DEFAULT_NOUN),
+            child_unit: DEFAULT_UNIT,
+            children: OnceLock::new(),
+            weight: AtomicU64::new(0.0_f64.to_bits()),
+            weighted_total: None,
+            initial_count: 0,
+            checkpoint: None,
+            checkpoint_lock: Mutex::new(()),
+            in_flight: OnceLock::new(),
+            on_message: OnceLock::new(),
+        }
+    }
+
+    fn lock_emissions(&self) -> MutexGuard<'_, Emissions> {
+        lock(&self.emissions)
+    }
+
+    /// Update the rate bookkeeping for a message emitted at `total`, returning the stats
+    /// collected since the previous message.
+    fn emitted(&self, total: u64) -> Interval {
+        let messages = self.messages.fetch_add(1, Ordering::Relaxed) + 1;
+        let now = Instant::now();
+        let mut emissions = self.lock_emissions();
+        emissions.last_message = now;
+        let elapsed = now.saturating_duration_since(emissions.at);
+        let mut slowdown = None;
+        // Messages from racing threads may arrive out of order, only move forward.
+        // A message without progress, i.e. from the heartbeat, counts as a rate of zero.
+        if total >= emissions.count && !elapsed.is_zero() {
+            let recent_rate = rate(total - emissions.count, elapsed);
+            slowdown = self.slowdown(recent_rate, emissions.peak_rate);
+            let ewma_rate = ewma(
+                emissions.ewma_rate,
+                recent_rate,
+                elapsed,
+                self.rate_half_life,
+            );
+            emissions.recent_rate = Some(recent_rate);
+            emissions.ewma_rate = Some(ewma_rate);
+            emissions.peak_rate = Some(
+                emissions
+                    .peak_rate
+                    .map_or(ewma_rate, |peak| peak.max(ewma_rate)),
+            );
+            if let Some(target_interval) = self.target_interval {
+                // Change by at most 10x per message, so one unusually fast or slow interval can't
+                // set a unit that takes hours to reach.
+                let unit = self.unit();
+                let target = nice_unit(ewma_rate * target_interval.as_secs_f64());
+                self.unit.store(
+                    target.clamp((unit / 10).max(1), unit.saturating_mul(10)),
+                    Ordering::Relaxed,
+                );
+            }
+            emissions.at = now;
+            emissions.count = total;
+        }
+        Interval {
+            values: self.interval_values.take(),
+            latency: self.interval_latencies.get().and_then(Histogram::take),
+            top_keys: self
+                .top_keys
+                .as_ref()
+                .filter(|_| self.top_keys_every > 0 && messages % self.top_keys_every == 0)
+                .map(TopKeysTracker::top),
+            slowdown,
+        }
+    }
+
+    /// Check `rate` against the expected minimum rate and the fraction of the peak rate, if set.
+    fn slowdown(&self, rate: f64, peak_rate: Option<f64>) -> Option<Slowdown> {
+        if let Some(min_rate) = self.min_rate.filter(|&min_rate| rate < min_rate) {
+            return Some(Slowdown::BelowMinimum { rate, min_rate });
+        }
+        let fraction = self.min_rate_of_peak?;
+        let peak_rate = peak_rate?;
+        (rate < fraction * peak_rate).then_some(Slowdown::BelowPeak { rate, peak_rate })
+    }
+
+    /// Format a count with the configured formatter.
+    #[cfg(feature = "pretty_counts")]
+    fn fmt_count(&self, count: u64) -> String {
+        self.count_formatter.fmt(count)
+    }
+
+    /// Format a count with the configured formatter.
+    #[cfg(not(feature = "pretty_counts"))]
+    fn fmt_count(&self, count: u64) -> u64 {
+        count
+    }
+
+    /// The level of the most severe cadence that `total` is a boundary of, if any.
+    ///
+    /// This is the configured `level` if `total` is a multiple of `unit`, and otherwise (or if
+    /// more severe) the level of any matching tier, see [`ProgLogBuilder::tier`]. Always `None`
+    /// while muted.
+    #[inline]
+    fn boundary(&self, total: u64) -> Option<Level> {
+        if self.is_muted() {
+            return None;
+        }
+        let mut level = (total % self.unit() == 0).then(|| self.level());
+        for &(unit, tier_level) in &self.tiers {
+            if total % unit == 0 {
+                level = Some(level.map_or(tier_level, |level| level.min(tier_level)));
+            }
+        }
+        level
+    }
+
+    /// Helper method to pull out log formatting .
+    #[inline]
+    fn log_it(&self, total: u64, level: Level) {
+        // Messages filtered out by the logger, i.e. a Debug tier under an Info filter, must not
+        // reset the interval stats or the rate of the messages that are emitted.
+        if self.is_muted() || !log::log_enabled!(level) {
+            return;
+        }
+        let interval = self.emitted(total);
+        log!(
+            interval.level(level),
+            "{}",
+            Progress {
+                log: self,
+                total,
+                interval
+            }
+        );
+        self.notify();
+    }
+
+    /// Helper method to pull out log formatting with custom user closure.
+    #[inline]
+    fn log_it_with<F, T>(&self, f: F, total: u64, level: Level)
+    where
+        F: Fn() -> T,
+        T: Display,
+    {
+        // Messages filtered out by the logger, i.e. a Debug tier under an Info filter, must not
+        // reset the interval stats or the rate of the messages that are emitted.
+        if self.is_muted() || !log::log_enabled!(level) {
+            return;
+        }
+        let interval = self.emitted(total);
+        log!(
+            interval.level(level),
+            "{}: {}",
+            Progress {
+                log: self,
+                total,
+                interval
+            },
+            f()
+        );
+        self.notify();
+    }
+
+    /// Call the `on_message` hook, if set.
+    fn notify(&self) {
+        if let Some(on_message) = self.on_message.get() {
+            on_message();
+        }
+    }
+
+    fn record(&self) -> bool {
+        let prev: u64 = self.fetch_add(1);
+        let total = prev + 1;
+        if let Some(level) = self.boundary(total) {
+            self.log_it(total, level);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn count(&self) -> u64 {
+        self.counter.load(Ordering::Relaxed)
+    }
+
+    fn unit(&self) -> u64 {
+        self.unit.load(Ordering::Relaxed)
+    }
+
+    /// The sum of the weights given to [`ProgLog::record_weighted`].
+    fn weight(&self) -> f64 {
+        f64::from_bits(self.weight.load(Ordering::Relaxed))
+    }
+
+    /// The percent complete and ETA by weight, if [`ProgLogBuilder::weighted_total`] is set.
+    ///
+    /// The ETA uses the weight rate over the lifetime of the logger, since the moving-average
+    /// rate is in items rather than weight.
+    fn weighted_progress(&self) -> Option<(f64, Option<Duration>)> {
+        let total = self.weighted_total.filter(|&total| total > 0.0)?;
+        let weight = self.weight();
+        let remaining = (total - weight).max(0.0);
+        let rate = weight / self.start.elapsed().as_secs_f64();
+        let eta = if remaining == 0.0 {
+            Some(Duration::ZERO)
+        } else if rate > 0.0 {
+            Duration::try_from_secs_f64(remaining / rate).ok()
+        } else {
+            None
+        };
+        Some((weight / total * 100.0, eta))
+    }
+
+    fn level(&self) -> Level {
+        match self.level.load(Ordering::Relaxed) {
+            1 => Level::Error,
+            2 => Level::Warn,
+            3 => Level::Info,
+            4 => Level::Debug,
+            _ => Level::Trace,
+        }
+    }
+
+    fn is_muted(&self) -> bool {
+        !self.enabled.load(Ordering::Relaxed)
+    }
+
+    fn fetch_add(&self, count: u64) -> u64 {
+        self.counter.fetch_add(count, Ordering::Relaxed)
+    }
+
+    /// The approximate number of distinct keys given to [`ProgLog::record_key`].
+    fn distinct(&self) -> Option<u64> {
+        self.distinct_keys.get().map(HyperLogLog::estimate)
+    }
+
+    /// The approximate fraction of keys given to [`ProgLog::record_key`] that were duplicates.
+    fn duplication(&self) -> Option<f64> {
+        let keys = self.keys.load(Ordering::Relaxed);
+        let distinct = self.distinct()?;
+        if keys == 0 {
+            return None;
+        }
+        Some(1.0 - distinct.min(keys) as f64 / keys as f64)
+    }
+
+    /// Log a summary of anything that isn't part of the regular progress messages.
+    fn summarize(&self) {
+        if self.is_muted() {
+            return;
+        }
+        let errors = self.errors.load(Ordering::Relaxed);
+        if errors > 0 {
+            let sample_errors = lock(&self.sample_errors);
+            log!(
+                self.level(),
+                "[{name}] {errors} errors in {results} results, first {sampled}:",
+                name = &self.name,
+                errors = self.fmt_count(errors),
+                results = self.fmt_count(self.results.load(Ordering::Relaxed)),
+                sampled = sample_errors.len()
+            );
+            for (seen, err) in sample_errors.iter() {
+                log!(
+                    self.level(),
+                    "[{name}]   #{seen}: {err}",
+                    name = &self.name,
+                    seen = self.fmt_count(*seen)
+                );
+            }
+        }
+        if let Some(top_keys) = &self.top_keys {
+            let top = top_keys.top();
+            log!(
+                self.level(),
+                "[{name}] Top {k} keys of {keyed} {noun}: {top}",
+                name = &self.name,
+                k = top_keys.k(),
+                keyed = self.fmt_count(top.keyed),
+                noun = &self.noun,
+            );
+        }
+        if let Some(latency) = self.latencies.get().and_then(Histogram::stats) {
+            log!(
+                self.level(),
+                "[{name}] Latency over {count} {noun}: {latency}",
+                name = &self.name,
+                count = self.fmt_count(latency.count),
+                noun = &self.noun,
+            );
+        }
+    }
+}
+
+impl Drop for ProgLog {
+    /// Drop the logger, stopping any background threads, then calling flush and logging a final
+    /// summary before dropping.
+    fn drop(&mut self) {
+        // Children dropped from now on must not emit messages after the final summary.
+        if let Some(children) = self.inner.children.get() {
+            children.close();
+        }
+        self.tickers.clear();
+        self.flush();
+        self.inner.summarize();
+        self.inner.save_checkpoint();
+        if let Some(parent) = self.parent.take() {
+            let finished = parent
+                .children
+                .get()
+                .is_some_and(|children| children.finish(&self.inner.counter));
+            if finished {
+                parent.record();
+            }
+        }
+    }
+}
+
+/// The builder for [`ProgLog`].
+pub struct ProgLogBuilder {
+    name: String,
+    noun: String,
+    verb: String,
+    unit: u64,
+    level: Level,
+    tiers: Vec<(u64, Level)>,
+    /// The formatter to use for outputting the current count.
+    #[cfg(feature = "pretty_counts")]
+    count_formatter: CountFormatterKind,
+    total: Option<u64>,
+    rate_half_life: Duration,
+    counters: Vec<String>,
+    sample_errors: usize,
+    value_name: String,
+    top_keys: Option<usize>,
+    top_keys_every: u64,
+    stall_timeout: Option<Duration>,
+    heartbeat: Option<Duration>,
+    min_rate: Option<f64>,
+    min_rate_of_peak: Option<f64>,
+    target_interval: Option<Duration>,
+    child_noun: String,
+    child_unit: u64,
+    weighted_total: Option<f64>,
+    initial_count: u64,
+    checkpoint: Option<PathBuf>,
+    checkpoint_period: Duration,
+    resume_from: Option<PathBuf>,
+    from_env: bool,
+    muted: bool,
+}
+
+impl ProgLogBuilder {
+    /// Create a new [`ProgLogBuilder`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The name of the logger, used so that multiple progress loggers can run at once.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// The noun used in the log output string format, ideally lowercase and plural.
+    pub fn noun(mut self, noun: impl Into<String>) -> Self {
+        self.noun = noun.into();
+        self
+    }
+
+    /// The verb used in the log output string format, ideally capitalized.
+    pub fn verb(mut self, verb: impl Into<String>) -> Self {
+        self.verb = verb.into();
+        self
+    }
+
+    /// How many items must be seen before emitting a log message.
+    pub fn unit(mut self, unit: u64) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// The [`log::Level`] at which to emit log messages.
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Add an additional cadence, emitting a message at `level` every `unit` items.
+    ///
+    /// This allows i.e. a [`Level::Debug`] message every 10,000 items and a [`Level::Info`] message
+    /// every 1,000,000 items. The `unit` and `level` of the builder are the primary tier. When a count
+    /// is a boundary of several tiers only one message is emitted, at the most severe of their levels,
+    /// and the closure given to [`ProgLog::record_with`] is only evaluated once.
+    ///
+    /// Tiers apply to [`ProgLog::record`] and friends, [`ProgLog::record_count`] and
+    /// [`ProgLog::record_count_with`] only use the primary tier. A `unit` of `0` is ignored. Messages
+    /// of a tier whose level is filtered out by the logger don't reset the interval stats or rates.
+    pub fn tier(mut self, unit: u64, level: Level) -> Self {
+        self.tiers.push((unit, level));
+        self
+    }
+
+    /// The formatter to use for outputting the current count.
+    #[cfg(feature = "pretty_counts")]
+    pub fn count_formatter(mut self, formatter: CountFormatterKind) -> Self {
+        self.count_formatter = formatter;
+        self
+    }
+
+    /// The expected total number of items, used for the percent complete and ETA.
+    pub fn total(mut self, total: u64) -> Self {
+        self.total = Some(total);
+        self
+    }
+
+    /// The half-life of the moving-average rate used for the ETA.
+    ///
+    /// A shorter half-life reacts faster to slowdowns, a longer one is steadier.
+    pub fn rate_half_life(mut self, half_life: Duration) -> Self {
+        self.rate_half_life = half_life;
+        self
+    }
+
+    /// Add an additional named counter, retrieved with [`ProgLog::counter`].
+    ///
+    /// Named counters are reported alongside the main count in each message, i.e.
+    /// `Processed 1000 records (kept 812, filtered 180, failed 8)`.
+    /// Only the main count drives the emission of messages.
+    pub fn counter(mut self, name: impl Into<String>) -> Self {
+        self.counters.push(name.into());
+        self
+    }
+
+    /// How many error messages from [`ProgLog::record_result`] to keep for the summary logged on drop.
+    pub fn sample_errors(mut self, sample_errors: usize) -> Self {
+        self.sample_errors = sample_errors;
+        self
+    }
+
+    /// The name of the values given to [`ProgLog::record_value`], i.e. `length`.
+    pub fn value_name(mut self, value_name: impl Into<String>) -> Self {
+        self.value_name = value_name.into();
+        self
+    }
+
+    /// Track the `k` most frequent keys given to [`ProgLog::record_keyed`].
+    ///
+    /// The top keys are reported in the summary logged on drop. Counts are approximate, using the
+    /// Space-Saving algorithm with a bounded number of counters.
+    pub fn top_keys(mut self, k: usize) -> Self {
+        self.top_keys = Some(k);
+        self
+    }
+
+    /// Also report the top keys in every `messages`th message, `0` to only report them in the summary.
+    pub fn top_keys_every(mut self, messages: u64) -> Self {
+        self.top_keys_every = messages;
+        self
+    }
+
+    /// Warn when the count has not advanced for `timeout`.
+    ///
+    /// Messages are normally only emitted from within the `record` methods, so a hung job goes silent.
+    /// This starts a watchdog thread that logs `[{name}] No progress for {timeout} (stuck at {seen} {noun})`
+    /// at [`Level::Warn`], repeating every `timeout` for as long as the count is stuck.
+    /// The thread is stopped when the [`ProgLog`] is dropped. A `timeout` below 1ms is treated as 1ms.
+    pub fn stall_timeout(mut self, timeout: Duration) -> Self {
+        self.stall_timeout = Some(timeout.max(MIN_PERIOD));
+        self
+    }
+
+    /// Emit a progress message whenever no message has been emitted for `period`.
+    ///
+    /// This is useful for slow items, where any `unit` either floods the log or stays silent for hours.
+    /// This starts a background thread that emits the same message as a `unit` boundary would, using the
+    /// current count. The thread is stopped when the [`ProgLog`] is dropped. A `period` below 1ms is
+    /// treated as 1ms.
+    pub fn heartbeat(mut self, period: Duration) -> Self {
+        self.heartbeat = Some(period.max(MIN_PERIOD));
+        self
+    }
+
+    /// The expected minimum rate, in items per second.
+    ///
+    /// When the rate since the previous message is below this, the message is escalated from the
+    /// configured `level` to [`Level::Warn`] and states the slowdown, so that stalls show up in logs
+    /// filtered at `Warn`.
+    pub fn min_rate(mut self, rate: f64) -> Self {
+        self.min_rate = Some(rate);
+        self
+    }
+
+    /// The expected minimum rate as a fraction of the peak rate seen so far, i.e. `0.5` for 50%.
+    ///
+    /// Messages are escalated as with [`ProgLogBuilder::min_rate`]. The peak is taken from the
+    /// moving-average rate, see [`ProgLogBuilder::rate_half_life`], so a single fast interval
+    /// doesn't set an unreachable peak.
+    pub fn min_rate_of_peak(mut self, fraction: f64) -> Self {
+        self.min_rate_of_peak = Some(fraction);
+        self
+    }
+
+    /// Automatically adjust `unit` so that a message is emitted about every `interval`.
+    ///
+    /// At each message the unit is recomputed from the moving-average rate and rounded down to a
+    /// "nice" number (1, 2 or 5 × 10^k), so messages are always at multiples of the current unit.
+    /// The unit changes by at most 10x per message, so it converges over a few messages rather
+    /// than jumping on a single unusually fast interval. The `unit` given to the builder is used
+    /// until the first message, so it should be small enough for a message to be emitted early on.
+    pub fn target_interval(mut self, interval: Duration) -> Self {
+        self.target_interval = Some(interval);
+        self
+    }
+
+    /// The noun used by the child loggers created with [`ProgLog::child`].
+    pub fn child_noun(mut self, noun: impl Into<String>) -> Self {
+        self.child_noun = noun.into();
+        self
+    }
+
+    /// How many items a child logger created with [`ProgLog::child`] must see before emitting a log message.
+    pub fn child_unit(mut self, unit: u64) -> Self {
+        self.child_unit = unit;
+        self
+    }
+
+    /// The expected total weight of the items given to [`ProgLog::record_weighted`].
+    ///
+    /// When set, the percent complete and ETA are computed from the weight instead of `total`.
+    pub fn weighted_total(mut self, total: f64) -> Self {
+        self.weighted_total = Some(total);
+        self
+    }
+
+    /// The count to start at, i.e. the number of items processed before a restart.
+    ///
+    /// Rates, and so the ETA, only count the items seen by this logger, so they are not skewed by
+    /// the work done before the restart.
+    pub fn initial_count(mut self, count: u64) -> Self {
+        self.initial_count = count;
+        self
+    }
+
+    /// Save the count and total to the file at `path` every [`ProgLogBuilder::checkpoint_period`],
+    /// and when the logger is dropped, so that it can be resumed with [`ProgLogBuilder::resume_from`].
+    ///
+    /// The file is written to a temporary file first and renamed over `path`, so it is never left
+    /// partially written. Failures to write it are logged at [`Level::Warn`].
+    pub fn checkpoint(mut self, path: impl Into<PathBuf>) -> Self {
+        self.checkpoint = Some(path.into());
+        self
+    }
+
+    /// How often to save the checkpoint, see [`ProgLogBuilder::checkpoint`]. A `period` below 1ms is
+    /// treated as 1ms.
+    pub fn checkpoint_period(mut self, period: Duration) -> Self {
+        self.checkpoint_period = period.max(MIN_PERIOD);
+        self
+    }
+
+    /// Restore the count and total from the checkpoint at `path`, see [`ProgLogBuilder::checkpoint`].
+    ///
+    /// The checkpoint takes precedence over [`ProgLogBuilder::initial_count`] and
+    /// [`ProgLogBuilder::total`]. If there is no file at `path` the logger starts from scratch,
+    /// and if the file can't be read or parsed this is logged at [`Level::Warn`] and ignored.
+    pub fn resume_from(mut self, path: impl Into<PathBuf>) -> Self {
+        self.resume_from = Some(path.into());
+        self
+    }
+
+    /// Allow the options to be overridden by environment variables when the logger is built.
+    ///
+    /// The variables are named after the logger, upper-cased with any other character than
+    /// `A-Z` and `0-9` replaced by `_`, i.e. for a logger named `read-pairs`:
+    ///
+    /// - `PROGLOG_READ_PAIRS_UNIT`: the `unit`, i.e. `1000000`.
+    /// - `PROGLOG_READ_PAIRS_LEVEL`: the `level`, i.e. `debug`.
+    /// - `PROGLOG_READ_PAIRS_INTERVAL`: the [`ProgLogBuilder::target_interval`], i.e. `30s`, `5m` or `1h`.
+    /// - `PROGLOG_READ_PAIRS_FORMAT`: the `count_formatter`, i.e. `comma`, requires the `pretty_counts` feature.
+    /// - `PROGLOG_DISABLE`: if `1`, `true`, `yes` or `on`, every such logger is built muted, see
+    ///   [`ProgLogBuilder::muted`]. A false value leaves the logger as configured.
+    ///
+    /// A variable that is set takes precedence over the value set in code, regardless of the order
+    /// the builder methods are called in, including values set from a `ProgressArgs` command line
+    /// (with the `clap` feature). Invalid values are logged at [`Level::Warn`] and ignored.
+    pub fn from_env(mut self) -> Self {
+        self.from_env = true;
+        self
+    }
+
+    /// Build the logger muted, see [`ProgLog::mute`].
+    pub fn muted(mut self, muted: bool) -> Self {
+        self.muted = muted;
+        self
+    }
+
+    /// Build the [`ProgLog`] instance.
+    pub fn build(mut self) -> ProgLog {
+        if self.from_env {
+            env::apply(&mut self);
+        }
+        if let Some(path) = &self.resume_from {
+            match checkpoint::read(path) {
+                Ok(Some(checkpoint)) => {
+                    self.initial_count = checkpoint.count;
+                    self.total = checkpoint.total.or(self.total);
+                }
+                Ok(None) => (),
+                Err(err) => warn!(
+                    "[{}] Ignoring checkpoint {}: {}",
+                    self.name,
+                    path.display(),
+                    err
+                ),
+            }
+        }
+        let mut inner = Inner::new(
+            self.name,
+            self.noun,
+            self.verb,
+            self.unit,
+            self.level,
+            #[cfg(feature = "pretty_counts")]
+            self.count_formatter,
+        );
+        inner.tiers = self
+            .tiers
+            .into_iter()
+            .filter(|&(unit, _)| unit > 0)
+            .collect();
+        inner.total = self.total;
+        inner.rate_half_life = self.rate_half_life;
+        inner.counters = self.counters.into_iter().map(Counter::new).collect();
+        inner.max_sample_errors = self.sample_errors;
+        inner.value_name = self.value_name;
+        inner.top_keys = self.top_keys.filter(|&k| k > 0).map(TopKeysTracker::new);
+        inner.top_keys_every = self.top_keys_every;
+        inner.target_interval = self.target_interval;
+        inner.min_rate = self.min_rate;
+        inner.min_rate_of_peak = self.min_rate_of_peak;
+        inner.child_noun = self.child_noun;
+        inner.child_unit = self.child_unit;
+        inner.weighted_total = self.weighted_total;
+        inner.initial_count = self.initial_count;
+        inner.counter.store(self.initial_count, Ordering::Relaxed);
+        inner.lock_emissions().count = self.initial_count;
+        inner.checkpoint = self.checkpoint;
+        let mut log = ProgLog::from_inner(inner);
+        if self.muted {
+            log.mute();
+        }
+        if let Some(timeout) = self.stall_timeout {
+            log.tickers
+                .push(ticker::stall_watchdog(Arc::clone(&log.inner), timeout));
+        }
+        if let Some(period) = self.heartbeat {
+            log.tickers
+                .push(ticker::heartbeat(Arc::clone(&log.inner), period));
+        }
+        if log.inner.checkpoint.is_some() {
+            log.tickers.push(checkpoint::checkpointer(
+                Arc::clone(&log.inner),
+                self.checkpoint_period,
+            ));
+        }
+        log
+    }
+}
+
+impl Default for ProgLogBuilder {
+    fn default() -> Self {
+        Self {
+            name: String::from(DEFAULT_NAME),
+            noun: String::from(DEFAULT_NOUN),
+            verb: String::from(DEFAULT_VERB),
+            unit: DEFAULT_UNIT,
+            level: DEFAULT_LEVEL,
+            tiers: Vec::new(),
+            #[cfg(feature = "pretty_counts")]
+            count_formatter: CountFormatterKind::Nothing,
+            total: None,
+            rate_half_life: DEFAULT_RATE_HALF_LIFE,
+            counters: Vec::new(),
+            sample_errors: DEFAULT_SAMPLE_ERRORS,
+            value_name: String::from(DEFAULT_VALUE_NAME),
+            top_keys: None,
+            top_keys_every: 0,
+            stall_timeout: None,
+            heartbeat: None,
+            min_rate: None,
+            min_rate_of_peak: None,
+            target_interval: None,
+            child_noun: String::from(DEFAULT_NOUN),
+            child_unit: DEFAULT_UNIT,
+            weighted_total: None,
+            initial_count: 0,
+            checkpoint: None,
+            checkpoint_period: DEFAULT_CHECKPOINT_PERIOD,
+            resume_from: None,
+            from_env: false,
+            muted: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use logtest::Logger;
+    use rayon::prelude::*;
+
+    fn drain_logger(logger: &mut Logger) {
+        while let Some(_msg) = logger.pop() {}
+        assert_eq!(logger.len(), 0);
+    }
+
+    /// This function drives all other tests since the logtest Logger is global :(.
+    ///
+    /// Each function called is expected to drain the logger.
+    #[test]
+    fn test_log_messages() {
+        let mut logger = logtest::Logger::start();
+        assert_eq!(logger.len(), 0);
+        test_simple_case(&mut logger);
+        drain_logger(&mut logger);
+
+        test_rayon(&mut logger);
+        drain_logger(&mut logger);
 
         test_messages_simple(&mut logger);
         drain_logger(&mut logger);
 
-        test_messages_simple_verify_unit(&mut logger);
+        test_messages_simple_verify_unit(&mut logger);
+        drain_logger(&mut logger);
+
+        test_snapshot(&mut logger);
+        drain_logger(&mut logger);
+
+        test_counters(&mut logger);
+        drain_logger(&mut logger);
+
+        test_record_result(&mut logger);
+        drain_logger(&mut logger);
+
+        test_record_value(&mut logger);
+        drain_logger(&mut logger);
+
+        test_latency(&mut logger);
+        drain_logger(&mut logger);
+
+        test_record_key(&mut logger);
+        drain_logger(&mut logger);
+
+        test_top_keys(&mut logger);
+        drain_logger(&mut logger);
+
+        test_stall_timeout(&mut logger);
+        drain_logger(&mut logger);
+
+        test_heartbeat(&mut logger);
+        drain_logger(&mut logger);
+
+        test_min_rate(&mut logger);
+        drain_logger(&mut logger);
+
+        test_target_interval(&mut logger);
+        drain_logger(&mut logger);
+
+        test_tiers(&mut logger);
+        drain_logger(&mut logger);
+
+        test_filtered_tiers(&mut logger);
+        drain_logger(&mut logger);
+
+        test_reconfigure(&mut logger);
+        drain_logger(&mut logger);
+
+        test_from_env(&mut logger);
+        drain_logger(&mut logger);
+
+        test_registry(&mut logger);
+        drain_logger(&mut logger);
+
+        test_children(&mut logger);
+        drain_logger(&mut logger);
+
+        test_pipeline(&mut logger);
+        drain_logger(&mut logger);
+
+        test_channel(&mut logger);
+        drain_logger(&mut logger);
+
+        test_gauge(&mut logger);
+        drain_logger(&mut logger);
+
+        test_record_weighted(&mut logger);
+        drain_logger(&mut logger);
+
+        test_checkpoint(&mut logger);
         drain_logger(&mut logger);
 
+        #[cfg(all(unix, feature = "signal"))]
+        {
+            test_dump_on_signal(&mut logger);
+            drain_logger(&mut logger);
+        }
+
         test_messages_rayon(&mut logger);
         drain_logger(&mut logger);
 
@@ -518,6 +1859,7 @@ mod tests {
 
         test_messages_count(&mut logger);
         drain_logger(&mut logger);
+
         #[cfg(feature = "pretty_counts")]
         {
             test_pretty_counts(&mut logger);
@@ -566,6 +1908,705 @@ mod tests {
         assert!(logger.pop().unwrap().args().ends_with("This is a test"));
     }
 
+    fn test_snapshot(logger: &mut Logger) {
+        let my_logger = ProgLogBuilder::new().unit(10).total(100).build();
+        for _ in 0..25 {
+            my_logger.record();
+        }
+        assert_eq!(logger.len(), 2);
+        let snapshot = my_logger.snapshot();
+        assert_eq!(snapshot.count, 25);
+        assert_eq!(snapshot.total, Some(100));
+        assert_eq!(snapshot.messages, 2);
+        assert_eq!(snapshot.percent, Some(25.0));
+        assert!(snapshot.recent_rate.is_some());
+        assert!(snapshot.ewma_rate.is_some());
+        assert!(snapshot.eta.is_some());
+        assert!(snapshot
+            .to_string()
+            .starts_with("[proglog] Processed 25/100 records (25.0%) in "));
+    }
+
+    fn test_counters(logger: &mut Logger) {
+        let my_logger = ProgLogBuilder::new()
+            .unit(10)
+            .counter("kept")
+            .counter("filtered")
+            .build();
+        (0..10).par_bridge().for_each(|i| {
+            if i % 5 == 0 {
+                my_logger.counter("filtered").record();
+            } else {
+                my_logger.counter("kept").record();
+            }
+            my_logger.record();
+        });
+        assert_eq!(my_logger.counter("kept").seen(), 8);
+        assert_eq!(my_logger.counter("filtered").seen(), 2);
+        my_logger.counter("kept").record_count(5);
+        assert_eq!(logger.len(), 1);
+        let msg = logger.pop().unwrap();
+        assert!(msg
+            .args()
+            .starts_with("[proglog] Processed 10 records (kept "));
+        assert_eq!(
+            my_logger.snapshot().counters,
+            vec![(String::from("kept"), 13), (String::from("filtered"), 2)]
+        );
+    }
+
+    fn test_record_result(logger: &mut Logger) {
+        let my_logger = ProgLogBuilder::new().unit(10).sample_errors(2).build();
+        for i in 0..20 {
+            let result = if i % 4 == 0 {
+                Err(format!("bad {}", i))
+            } else {
+                Ok(i)
+            };
+            my_logger.record_result(&result);
+        }
+        assert_eq!(my_logger.snapshot().errors, 5);
+        assert_eq!(logger.len(), 2);
+        assert_eq!(
+            logger.pop().unwrap().args(),
+            "[proglog] Processed 10 records; 3 errors (30.00%)"
+        );
+        assert_eq!(
+            logger.pop().unwrap().args(),
+            "[proglog] Processed 20 records; 5 errors (25.00%)"
+        );
+        drop(my_logger);
+        let summary: Vec<_> = logger.map(|msg| msg.args().to_owned()).collect();
+        assert_eq!(
+            summary,
+            vec![
+                "[proglog] 5 errors in 20 results, first 2:",
+                "[proglog]   #1: bad 0",
+                "[proglog]   #5: bad 4",
+            ]
+        );
+    }
+
+    fn test_record_value(logger: &mut Logger) {
+        let my_logger = ProgLogBuilder::new().unit(4).value_name("length").build();
+        for value in [10, 20, 30, 40, 1, 1, 1, 1] {
+            my_logger.record_value(value);
+        }
+        assert_eq!(logger.len(), 2);
+        assert_eq!(
+            logger.pop().unwrap().args(),
+            "[proglog] Processed 4 records; length sum 100 mean 25.0 min 10 max 40 (interval sum 100 mean 25.0 min 10 max 40)"
+        );
+        assert_eq!(
+            logger.pop().unwrap().args(),
+            "[proglog] Processed 8 records; length sum 104 mean 13.0 min 1 max 40 (interval sum 4 mean 1.0 min 1 max 1)"
+        );
+        let values = my_logger.snapshot().values.unwrap();
+        assert_eq!(values.count, 8);
+        assert_eq!(values.mean(), Some(13.0));
+    }
+
+    fn test_latency(logger: &mut Logger) {
+        let my_logger = ProgLogBuilder::new().unit(2).build();
+        assert_eq!(my_logger.time(|| 42), 42);
+        {
+            let _timer = my_logger.start_item();
+        }
+        my_logger.record();
+        assert_eq!(my_logger.seen(), 3);
+        assert_eq!(logger.len(), 1);
+        assert!(logger
+            .pop()
+            .unwrap()
+            .args()
+            .starts_with("[proglog] Processed 2 records; latency p50 "));
+        assert_eq!(my_logger.snapshot().latency.unwrap().count, 2);
+        drop(my_logger);
+        assert_eq!(logger.len(), 2);
+        assert_eq!(
+            logger.pop().unwrap().args(),
+            "[proglog] Processed 3 records"
+        );
+        assert!(logger
+            .pop()
+            .unwrap()
+            .args()
+            .starts_with("[proglog] Latency over 2 records: p50 "));
+    }
+
+    fn test_record_key(logger: &mut Logger) {
+        let my_logger = ProgLogBuilder::new().unit(100).build();
+        (0..100).par_bridge().for_each(|i| {
+            my_logger.record_key(&format!("key{}", i % 25));
+        });
+        assert_eq!(logger.len(), 1);
+        assert_eq!(
+            logger.pop().unwrap().args(),
+            "[proglog] Processed 100 records; ~25 distinct keys (75.00% duplicates)"
+        );
+        let snapshot = my_logger.snapshot();
+        assert_eq!(snapshot.distinct, Some(25));
+        assert_eq!(snapshot.duplication, Some(0.75));
+    }
+
+    fn test_top_keys(logger: &mut Logger) {
+        let my_logger = ProgLogBuilder::new()
+            .unit(10)
+            .top_keys(2)
+            .top_keys_every(2)
+            .build();
+        let contigs = ["chr1", "chr2", "chr1", "chr3", "chr1", "chr2"];
+        (0..20).par_bridge().for_each(|i| {
+            my_logger.record_keyed(contigs[i % contigs.len()]);
+        });
+        assert_eq!(logger.len(), 2);
+        assert_eq!(
+            logger.pop().unwrap().args(),
+            "[proglog] Processed 10 records"
+        );
+        assert_eq!(
+            logger.pop().unwrap().args(),
+            "[proglog] Processed 20 records; top keys chr1 10 (50.00%), chr2 7 (35.00%)"
+        );
+        drop(my_logger);
+        assert_eq!(
+            logger.pop().unwrap().args(),
+            "[proglog] Top 2 keys of 20 records: chr1 10 (50.00%), chr2 7 (35.00%)"
+        );
+    }
+
+    fn test_stall_timeout(logger: &mut Logger) {
+        let my_logger = ProgLogBuilder::new()
+            .unit(10)
+            .stall_timeout(Duration::from_millis(50))
+            .build();
+        for _ in 0..5 {
+            my_logger.record();
+        }
+        let start = Instant::now();
+        while logger.is_empty() && start.elapsed() < Duration::from_secs(10) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        drop(my_logger);
+        let warnings: Vec<_> = logger
+            .filter(|msg| msg.level() == Level::Warn)
+            .map(|msg| msg.args().to_owned())
+            .collect();
+        assert!(!warnings.is_empty());
+        assert!(warnings[0].starts_with("[proglog] No progress for 0."));
+        assert!(warnings[0].ends_with("s (stuck at 5 records)"));
+    }
+
+    fn test_heartbeat(logger: &mut Logger) {
+        let my_logger = ProgLogBuilder::new()
+            .unit(1_000)
+            .heartbeat(Duration::from_millis(50))
+            .build();
+        for _ in 0..5 {
+            my_logger.record();
+        }
+        let start = Instant::now();
+        while logger.is_empty() && start.elapsed() < Duration::from_secs(10) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let heartbeats = logger.len();
+        assert!(heartbeats >= 1);
+        assert_eq!(
+            logger.pop().unwrap().args(),
+            "[proglog] Processed 5 records"
+        );
+        assert!(my_logger.snapshot().messages >= heartbeats as u64);
+    }
+
+    fn test_min_rate(logger: &mut Logger) {
+        let my_logger = ProgLogBuilder::new().unit(10).min_rate(1e15).build();
+        for _ in 0..20 {
+            my_logger.record();
+        }
+        assert_eq!(logger.len(), 2);
+        for _ in 0..2 {
+            let msg = logger.pop().unwrap();
+            assert_eq!(msg.level(), Level::Warn);
+            assert!(msg.args().contains(" records; slow: "), "{}", msg.args());
+            assert!(msg
+                .args()
+                .ends_with(" records/s is below the expected 1000000000000000.0 records/s"));
+        }
+
+        let my_logger = ProgLogBuilder::new().unit(10).min_rate_of_peak(0.5).build();
+        for _ in 0..10 {
+            my_logger.record();
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        for _ in 0..10 {
+            my_logger.record();
+        }
+        assert_eq!(logger.len(), 2);
+        assert_eq!(logger.pop().unwrap().level(), Level::Info);
+        let msg = logger.pop().unwrap();
+        assert_eq!(msg.level(), Level::Warn);
+        assert!(msg.args().contains("% of the peak "), "{}", msg.args());
+    }
+
+    fn test_target_interval(logger: &mut Logger) {
+        let my_logger = ProgLogBuilder::new()
+            .unit(1)
+            .target_interval(Duration::from_secs(3600))
+            .build();
+        for _ in 0..100 {
+            my_logger.record();
+        }
+        // 1 -> 10 -> 100 -> 1000, at most 10x per message.
+        assert!(logger.len() >= 3);
+        let unit = my_logger.snapshot().unit;
+        assert_eq!(unit, 1_000);
+        assert_eq!(unit, nice_unit(unit as f64));
+    }
+
+    fn test_tiers(logger: &mut Logger) {
+        let evaluated = AtomicU64::new(0);
+        let my_logger = ProgLogBuilder::new()
+            .unit(100)
+            .tier(10, Level::Debug)
+            .tier(50, Level::Warn)
+            .build();
+        for _ in 0..200 {
+            my_logger.record_with(|| evaluated.fetch_add(1, Ordering::Relaxed));
+        }
+        assert_eq!(logger.len(), 20);
+        assert_eq!(evaluated.load(Ordering::Relaxed), 20);
+        for i in 1..=20 {
+            let msg = logger.pop().unwrap();
+            let expected = if i % 5 == 0 {
+                Level::Warn
+            } else {
+                Level::Debug
+            };
+            assert_eq!(msg.level(), expected, "{}", msg.args());
+            assert!(msg
+                .args()
+                .starts_with(&format!("[proglog] Processed {} records", i * 10)));
+        }
+    }
+
+    fn test_filtered_tiers(logger: &mut Logger) {
+        log::set_max_level(log::LevelFilter::Info);
+        let my_logger = ProgLogBuilder::new()
+            .unit(100)
+            .tier(10, Level::Debug)
+            .build();
+        for _ in 0..200 {
+            my_logger.record();
+        }
+        log::set_max_level(log::LevelFilter::Trace);
+        assert_eq!(logger.len(), 2);
+        assert_eq!(my_logger.snapshot().messages, 2);
+    }
+
+    fn test_reconfigure(logger: &mut Logger) {
+        let my_logger = ProgLogBuilder::new().unit(10).build();
+        for _ in 0..25 {
+            my_logger.record();
+        }
+        assert_eq!(logger.len(), 2);
+        drain_logger(logger);
+
+        my_logger.set_unit(20);
+        my_logger.set_level(Level::Debug);
+        assert_eq!((my_logger.unit(), my_logger.level()), (20, Level::Debug));
+        for _ in 25..60 {
+            my_logger.record();
+        }
+        assert_eq!(logger.len(), 2);
+        for seen in [40, 60] {
+            let msg = logger.pop().unwrap();
+            assert_eq!(msg.level(), Level::Debug);
+            assert_eq!(msg.args(), format!("[proglog] Processed {} records", seen));
+        }
+
+        my_logger.mute();
+        assert!(my_logger.is_muted());
+        for _ in 60..100 {
+            assert!(!my_logger.record());
+        }
+        my_logger.flush();
+        assert_eq!(logger.len(), 0);
+
+        my_logger.unmute();
+        for _ in 100..120 {
+            my_logger.record();
+        }
+        assert_eq!(logger.len(), 1);
+        assert_eq!(
+            logger.pop().unwrap().args(),
+            "[proglog] Processed 120 records"
+        );
+    }
+
+    fn test_from_env(logger: &mut Logger) {
+        std::env::set_var("PROGLOG_ENV_TEST_UNIT", "5");
+        std::env::set_var("PROGLOG_ENV_TEST_LEVEL", "debug");
+        std::env::set_var("PROGLOG_ENV_TEST_INTERVAL", "soon");
+        let my_logger = ProgLogBuilder::new()
+            .name("env-test")
+            .unit(100)
+            .from_env()
+            .build();
+        let msg = logger.pop().unwrap();
+        assert_eq!(msg.level(), Level::Warn);
+        assert!(msg
+            .args()
+            .starts_with("[env-test] Ignoring PROGLOG_ENV_TEST_INTERVAL=\"soon\": "));
+        for _ in 0..10 {
+            my_logger.record();
+        }
+        assert_eq!(logger.len(), 2);
+        assert_eq!(logger.pop().unwrap().level(), Level::Debug);
+        drain_logger(logger);
+
+        // Without `from_env` the code wins.
+        let my_logger = ProgLogBuilder::new().name("env-test").unit(100).build();
+        assert_eq!((my_logger.unit(), my_logger.level()), (100, Level::Info));
+
+        for name in ["UNIT", "LEVEL", "INTERVAL"] {
+            std::env::remove_var(format!("PROGLOG_ENV_TEST_{}", name));
+        }
+        std::env::set_var(env::DISABLE_VAR, "true");
+        let my_logger = ProgLogBuilder::new().name("env-test").from_env().build();
+        std::env::remove_var(env::DISABLE_VAR);
+        assert!(my_logger.is_muted());
+        for _ in 0..10 {
+            my_logger.record();
+        }
+        drop(my_logger);
+        assert_eq!(logger.len(), 0);
+
+        // A false value doesn't unmute.
+        std::env::set_var(env::DISABLE_VAR, "0");
+        let my_logger = ProgLogBuilder::new()
+            .name("env-test")
+            .muted(true)
+            .from_env()
+            .build();
+        std::env::remove_var(env::DISABLE_VAR);
+        assert!(my_logger.is_muted());
+        drop(my_logger);
+        assert_eq!(logger.len(), 0);
+    }
+
+    fn test_registry(logger: &mut Logger) {
+        let reader = get_or_init("registry-reader", || ProgLogBuilder::new().unit(10));
+        let writer = get_or_init("registry-writer", ProgLogBuilder::new);
+        assert_eq!(reader.unit(), 10);
+        for _ in 0..15 {
+            get_or_init("registry-reader", || panic!("already registered")).record();
+        }
+        assert_eq!(reader.seen(), 15);
+        assert!(Arc::ptr_eq(&writer, &get("registry-writer").unwrap()));
+        let names: Vec<_> = loggers()
+            .iter()
+            .map(|log| log.snapshot().name)
+            .filter(|name| name.starts_with("registry-"))
+            .collect();
+        assert_eq!(names, vec!["registry-reader", "registry-writer"]);
+        assert_eq!(logger.len(), 1);
+        drain_logger(logger);
+
+        // The final message is only emitted once the last handle is dropped.
+        drop(remove("registry-reader"));
+        assert!(get("registry-reader").is_none());
+        assert_eq!(logger.len(), 0);
+        drop(reader);
+        assert_eq!(
+            logger.pop().unwrap().args(),
+            "[registry-reader] Processed 15 records"
+        );
+        drop(writer);
+        assert!(remove("registry-writer").is_some());
+        assert!(remove("registry-writer").is_none());
+    }
+
+    #[cfg(all(unix, feature = "signal"))]
+    fn test_dump_on_signal(logger: &mut Logger) {
+        let log = get_or_init("signal", || ProgLogBuilder::new().total(100));
+        for _ in 0..50 {
+            log.record();
+        }
+        dump_on_signal().unwrap();
+        dump_on_signal().unwrap();
+        signal_hook::low_level::raise(signal_hook::consts::SIGUSR1).unwrap();
+        let start = Instant::now();
+        while logger.is_empty() && start.elapsed() < Duration::from_secs(10) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let msg = logger.pop().unwrap();
+        assert_eq!(msg.level(), Level::Info);
+        assert!(msg
+            .args()
+            .starts_with("[signal] Processed 50/100 records (50.0%) in "));
+        drop(remove("signal"));
+    }
+
+    fn test_children(logger: &mut Logger) {
+        let files = ProgLogBuilder::new()
+            .noun("files")
+            .unit(2)
+            .total(3)
+            .child_unit(10)
+            .build();
+        let file = files.child("a.bam");
+        for _ in 0..15 {
+            file.record();
+        }
+        drop(file);
+        let messages: Vec<_> = std::iter::from_fn(|| logger.pop())
+            .map(|msg| msg.args().to_owned())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "[proglog/a.bam] Processed 10 records",
+                "[proglog/a.bam] Processed 15 records",
+            ]
+        );
+
+        let file = files.child("b.bam");
+        for _ in 0..5 {
+            file.record();
+        }
+        files.flush();
+        assert_eq!(
+            logger.pop().unwrap().args(),
+            "[proglog] Processed 1/3 files; 5 records in progress; 20 records total"
+        );
+        drop(file);
+        drain_logger(logger);
+        assert_eq!(files.seen(), 2);
+        assert_eq!(files.inner.children.get().unwrap().counts(), (0, 20));
+
+        // A child outliving its parent doesn't emit messages for the parent.
+        let file = files.child("c.bam");
+        drop(files);
+        drain_logger(logger);
+        file.record();
+        drop(file);
+        assert_eq!(
+            logger.pop().unwrap().args(),
+            "[proglog/c.bam] Processed 1 records"
+        );
+        assert!(logger.is_empty());
+    }
+
+    fn test_pipeline(logger: &mut Logger) {
+        let pipeline = PipelineBuilder::new()
+            .stage("read", ProgLogBuilder::new().unit(10))
+            .stage("parsed", ProgLogBuilder::new().unit(10))
+            .stage("written", ProgLogBuilder::new().unit(10))
+            .build();
+        for _ in 0..5 {
+            pipeline.stage("parsed").record();
+        }
+        for _ in 0..20 {
+            pipeline.stage("read").record();
+        }
+        let messages: Vec<_> = std::iter::from_fn(|| logger.pop())
+            .map(|msg| msg.args().to_owned())
+            .collect();
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0], "[pipeline/read] Processed 10 records");
+        assert!(messages[1].starts_with(
+            "[pipeline] read 10 | parsed 5 | written 0; lag parsed 5, written 5; bottleneck written at "
+        ));
+        assert_eq!(messages[2], "[pipeline/read] Processed 20 records");
+        assert!(messages[3].starts_with(
+            "[pipeline] read 20 | parsed 5 | written 0; lag parsed 15, written 5; bottleneck "
+        ));
+        let labels: Vec<_> = pipeline.stages().map(|(label, _)| label).collect();
+        assert_eq!(labels, vec!["read", "parsed", "written"]);
+    }
+
+    fn test_channel(logger: &mut Logger) {
+        let (tx, rx) = channel::channel(
+            ProgLogBuilder::new().name("sent").unit(5).build(),
+            ProgLogBuilder::new().name("received").unit(5).build(),
+        );
+        for i in 0..10 {
+            tx.clone().send(i).unwrap();
+        }
+        assert_eq!(rx.iter().take(5).sum::<u64>(), 10);
+        assert_eq!((tx.in_flight(), rx.in_flight()), (5, 5));
+        drop(tx);
+        assert_eq!(rx.into_iter().count(), 5);
+        let messages: Vec<_> = std::iter::from_fn(|| logger.pop())
+            .map(|msg| msg.args().to_owned())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "[sent] Processed 5 records; 5 in flight",
+                "[sent] Processed 10 records; 10 in flight",
+                "[received] Processed 5 records; 5 in flight",
+                "[received] Processed 10 records; 0 in flight",
+            ]
+        );
+
+        #[cfg(feature = "crossbeam")]
+        {
+            let (tx, rx) = channel::bounded(
+                4,
+                ProgLogBuilder::new().name("sent").unit(5).build(),
+                ProgLogBuilder::new().name("received").unit(5).build(),
+            );
+            std::thread::spawn(move || (0..10).for_each(|i| tx.send(i).unwrap()));
+            assert_eq!(rx.iter().sum::<u64>(), 45);
+            assert_eq!(rx.log().seen(), 10);
+        }
+    }
+
+    fn test_gauge(logger: &mut Logger) {
+        let gauge = GaugeBuilder::new()
+            .name("queue")
+            .noun("batches")
+            .threshold(10)
+            .build();
+        for _ in 0..12 {
+            gauge.inc();
+        }
+        assert_eq!(gauge.sub(3), 9);
+        assert_eq!(gauge.set(100), 100);
+        assert_eq!(gauge.set(50), 50);
+        assert_eq!(gauge.sub(60), 0);
+        assert_eq!(gauge.dec(), 0);
+        drop(gauge);
+        let messages: Vec<_> = std::iter::from_fn(|| logger.pop())
+            .map(|msg| msg.args().to_owned())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "[queue] Holding 10 batches",
+                "[queue] Holding 9 batches",
+                "[queue] Holding 100 batches",
+                "[queue] Holding 0 batches",
+                "[queue] Holding 0 batches",
+            ]
+        );
+    }
+
+    fn test_record_weighted(logger: &mut Logger) {
+        let my_logger = ProgLogBuilder::new()
+            .unit(5)
+            .total(1_000)
+            .weighted_total(100.0)
+            .build();
+        my_logger.record_weighted(47.5);
+        for _ in 0..4 {
+            my_logger.record_weighted(0.625);
+        }
+        my_logger.record_weighted(f64::NAN);
+        assert_eq!(logger.len(), 1);
+        let msg = logger.pop().unwrap();
+        assert!(
+            msg.args()
+                .starts_with("[proglog] Processed 5 records; 50.0% complete, ETA "),
+            "{}",
+            msg.args()
+        );
+        let snapshot = my_logger.snapshot();
+        assert_eq!(snapshot.count, 6);
+        assert_eq!(snapshot.weight, Some(50.0));
+        assert_eq!(snapshot.percent, Some(50.0));
+        assert!(snapshot.eta.is_some());
+    }
+
+    fn test_checkpoint(logger: &mut Logger) {
+        let my_logger = ProgLogBuilder::new().unit(10).initial_count(100).build();
+        for _ in 0..15 {
+            my_logger.record();
+        }
+        assert_eq!(logger.len(), 1);
+        assert_eq!(
+            logger.pop().unwrap().args(),
+            "[proglog] Processed 110 records"
+        );
+        let snapshot = my_logger.snapshot();
+        assert_eq!(snapshot.count, 115);
+        assert!(snapshot.rate <= rate(15, snapshot.elapsed));
+        drop(my_logger);
+        drain_logger(logger);
+
+        let path = std::env::temp_dir().join(format!("proglog-test-{}.ckpt", std::process::id()));
+        let my_logger = ProgLogBuilder::new()
+            .unit(1_000)
+            .total(500)
+            .checkpoint(&path)
+            .checkpoint_period(Duration::from_secs(3600))
+            .resume_from(&path)
+            .build();
+        for _ in 0..42 {
+            my_logger.record();
+        }
+        drop(my_logger);
+        drain_logger(logger);
+
+        let my_logger = ProgLogBuilder::new().resume_from(&path).build();
+        assert_eq!(my_logger.seen(), 42);
+        assert_eq!(my_logger.snapshot().total, Some(500));
+        drop(my_logger);
+        drain_logger(logger);
+
+        std::fs::write(&path, "garbage").unwrap();
+        let my_logger = ProgLogBuilder::new().resume_from(&path).build();
+        assert_eq!(my_logger.seen(), 0);
+        let msg = logger.pop().unwrap();
+        assert_eq!(msg.level(), Level::Warn);
+        assert!(msg.args().starts_with("[proglog] Ignoring checkpoint "));
+        std::fs::remove_file(&path).unwrap();
+
+        let builder = ProgLogBuilder::new()
+            .checkpoint_period(Duration::ZERO)
+            .heartbeat(Duration::ZERO)
+            .stall_timeout(Duration::ZERO);
+        assert_eq!(builder.checkpoint_period, MIN_PERIOD);
+        assert_eq!(builder.heartbeat, Some(MIN_PERIOD));
+        assert_eq!(builder.stall_timeout, Some(MIN_PERIOD));
+    }
+
+    #[test]
+    fn test_nice_unit() {
+        assert_eq!(nice_unit(0.0), 1);
+        assert_eq!(nice_unit(f64::NAN), 1);
+        assert_eq!(nice_unit(1.9), 1);
+        assert_eq!(nice_unit(2.0), 2);
+        assert_eq!(nice_unit(49.0), 20);
+        assert_eq!(nice_unit(1_000.0), 1_000);
+        assert_eq!(nice_unit(7_654_321.0), 5_000_000);
+        assert_eq!(nice_unit(1e15), 1_000_000_000_000_000);
+    }
+
+    #[test]
+    fn test_eta() {
+        assert_eq!(eta(0, 0.0), Some(Duration::ZERO));
+        assert_eq!(eta(10, 0.0), None);
+        assert_eq!(eta(10, 2.0), Some(Duration::from_secs(5)));
+        assert_eq!(eta(1_000_000, f64::MIN_POSITIVE), None);
+    }
+
+    #[test]
+    fn test_ewma() {
+        let half_life = Duration::from_secs(10);
+        assert_eq!(ewma(None, 100.0, Duration::from_secs(1), half_life), 100.0);
+        assert_eq!(
+            ewma(Some(100.0), 200.0, Duration::from_secs(10), half_life),
+            150.0
+        );
+        assert!(ewma(Some(100.0), 200.0, Duration::from_secs(1), half_life) < 150.0);
+        assert_eq!(
+            ewma(Some(100.0), 200.0, Duration::from_secs(1), Duration::ZERO),
+            200.0
+        );
+    }
+
     fn test_messages_simple_verify_unit(logger: &mut Logger) {
         let my_logger = ProgLogBuilder::new().unit(10).build();
         for _ in 0..9 {
@@ -0,0 +1,239 @@
+//! A combined view of the stages of a pipeline, i.e. reader → parser → writer.
+use crate::{lock, rate, ticker::Ticker, ProgLog, ProgLogBuilder, DEFAULT_LEVEL};
+use log::{log, Level};
+use std::{
+    fmt::{self, Display},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+static DEFAULT_PIPELINE_NAME: &str = "pipeline";
+
+/// A group of [`ProgLog`]s, one per stage of a pipeline, that also emits a combined message for
+/// all of the stages.
+///
+/// The combined message is emitted each time the first stage emits a message, and optionally
+/// every [`PipelineBuilder::interval`], and is formatted as:
+///
+/// ```text
+/// [{name}] read 10000000 | parsed 9800000 | written 9700000; lag parsed 200000, written 100000; bottleneck written at 95000.0 records/s
+/// ```
+///
+/// The lag of a stage is how far it is behind the stage before it. The bottleneck is the stage
+/// with the lowest rate since the previous combined message. Each stage still counts with its
+/// own atomics and emits its own messages, mute a stage with [`ProgLog::mute`] to only see the
+/// combined messages, the first stage must stay unmuted for them to be emitted on its boundaries.
+///
+/// ```rust
+/// use proglog::{PipelineBuilder, ProgLogBuilder};
+///
+/// let pipeline = PipelineBuilder::new()
+///     .stage("read", ProgLogBuilder::new().unit(1_000))
+///     .stage("written", ProgLogBuilder::new().unit(1_000))
+///     .build();
+/// for _ in 0..10_000 {
+///     pipeline.stage("read").record();
+///     pipeline.stage("written").record();
+/// }
+/// ```
+pub struct Pipeline {
+    stages: Arc<Stages>,
+    /// Emits the combined message on a timer, if enabled, stopped on drop.
+    ticker: Option<Ticker>,
+}
+
+/// The state of a [`Pipeline`], shared with the first stage and the timer thread.
+struct Stages {
+    name: String,
+    level: Level,
+    stages: Vec<(String, ProgLog)>,
+    /// When the previous combined message was emitted and the stage counts at that time.
+    previous: Mutex<(Instant, Vec<u64>)>,
+}
+
+impl Pipeline {
+    /// Get the stage `label`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `label` was not added with [`PipelineBuilder::stage`].
+    pub fn stage(&self, label: &str) -> &ProgLog {
+        self.stages
+            .stages
+            .iter()
+            .find(|(stage, _)| stage == label)
+            .map(|(_, log)| log)
+            .unwrap_or_else(|| panic!("[{}] No stage named `{}`", self.stages.name, label))
+    }
+
+    /// Iterate over the labels and loggers of the stages, in order.
+    pub fn stages(&self) -> impl Iterator<Item = (&str, &ProgLog)> {
+        self.stages
+            .stages
+            .iter()
+            .map(|(label, log)| (label.as_str(), log))
+    }
+
+    /// Force the output of a combined message.
+    pub fn flush(&self) {
+        self.stages.log_it();
+    }
+}
+
+impl Drop for Pipeline {
+    /// Stop the timer and emit a final combined message, the stages are then dropped in order.
+    fn drop(&mut self) {
+        self.ticker = None;
+        self.flush();
+    }
+}
+
+impl Stages {
+    fn log_it(&self) {
+        let mut previous = lock(&self.previous);
+        let now = Instant::now();
+        let counts: Vec<u64> = self.stages.iter().map(|(_, log)| log.seen()).collect();
+        let elapsed = now.saturating_duration_since(previous.0);
+        // The stage with the lowest rate since the previous message.
+        let bottleneck = (self.stages.len() > 1 && !elapsed.is_zero())
+            .then(|| {
+                counts
+                    .iter()
+                    .zip(&previous.1)
+                    .map(|(count, prev)| rate(count.saturating_sub(*prev), elapsed))
+                    .enumerate()
+                    .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            })
+            .flatten();
+        log!(
+            self.level,
+            "{}",
+            Combined {
+                stages: self,
+                counts: &counts,
+                bottleneck
+            }
+        );
+        *previous = (now, counts);
+    }
+}
+
+/// The body of a combined message, formatted lazily.
+struct Combined<'a> {
+    stages: &'a Stages,
+    counts: &'a [u64],
+    bottleneck: Option<(usize, f64)>,
+}
+
+impl Display for Combined<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stages = &self.stages.stages;
+        write!(f, "[{}]", self.stages.name)?;
+        for (i, ((label, log), count)) in stages.iter().zip(self.counts).enumerate() {
+            let sep = if i == 0 { " " } else { " | " };
+            write!(f, "{}{} {}", sep, label, log.inner.fmt_count(*count))?;
+        }
+        for (i, window) in self.counts.windows(2).enumerate() {
+            let sep = if i == 0 { "; lag " } else { ", " };
+            let (label, log) = &stages[i + 1];
+            let lag = window[0].saturating_sub(window[1]);
+            write!(f, "{}{} {}", sep, label, log.inner.fmt_count(lag))?;
+        }
+        if let Some((i, rate)) = self.bottleneck {
+            let (label, log) = &stages[i];
+            write!(
+                f,
+                "; bottleneck {} at {:.1} {}/s",
+                label, rate, log.inner.noun
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The builder for [`Pipeline`].
+pub struct PipelineBuilder {
+    name: String,
+    level: Level,
+    stages: Vec<(String, ProgLogBuilder)>,
+    interval: Option<Duration>,
+}
+
+impl PipelineBuilder {
+    /// Create a new [`PipelineBuilder`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The name of the pipeline, used in the combined message and to name the stages `{name}/{label}`.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// The [`log::Level`] at which to emit the combined messages.
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Add a stage, labelled `label` in the combined message, counted by a logger built from `builder`.
+    pub fn stage(mut self, label: impl Into<String>, builder: ProgLogBuilder) -> Self {
+        self.stages.push((label.into(), builder));
+        self
+    }
+
+    /// Also emit the combined message every `interval`.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Build the [`Pipeline`] instance.
+    pub fn build(self) -> Pipeline {
+        let name = self.name;
+        let stages: Vec<(String, ProgLog)> = self
+            .stages
+            .into_iter()
+            .map(|(label, builder)| {
+                let log = builder.name(format!("{}/{}", name, label)).build();
+                (label, log)
+            })
+            .collect();
+        let counts = vec![0; stages.len()];
+        let stages = Arc::new(Stages {
+            name,
+            level: self.level,
+            stages,
+            previous: Mutex::new((Instant::now(), counts)),
+        });
+        if let Some((_, first)) = stages.stages.first() {
+            let weak = Arc::downgrade(&stages);
+            let _ = first.inner.on_message.set(Box::new(move || {
+                if let Some(stages) = weak.upgrade() {
+                    stages.log_it();
+                }
+            }));
+        }
+        let ticker = self.interval.map(|interval| {
+            let shared = Arc::clone(&stages);
+            Ticker::spawn(
+                format!("proglog-pipeline-{}", shared.name),
+                interval,
+                move || shared.log_it(),
+            )
+        });
+        Pipeline { stages, ticker }
+    }
+}
+
+impl Default for PipelineBuilder {
+    fn default() -> Self {
+        Self {
+            name: String::from(DEFAULT_PIPELINE_NAME),
+            level: DEFAULT_LEVEL,
+            stages: Vec::new(),
+            interval: None,
+        }
+    }
+}
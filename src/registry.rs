@@ -0,0 +1,61 @@
+//! A process-wide registry of named progress loggers.
+use crate::{lock, ProgLog, ProgLogBuilder};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+static REGISTRY: Mutex<BTreeMap<String, Arc<ProgLog>>> = Mutex::new(BTreeMap::new());
+
+/// Get the registered logger `name`, building and registering it with the builder returned by
+/// `init` if there is none.
+///
+/// This allows a logger to be shared from anywhere in a program without passing it around. The
+/// logger is always named `name`, regardless of the name set on the builder.
+///
+/// `init` is called with the registry locked, so it must not call any of the registry functions.
+///
+/// ```rust
+/// use proglog::ProgLogBuilder;
+///
+/// let reader = proglog::get_or_init("reader", || ProgLogBuilder::new().noun("reads"));
+/// reader.record();
+/// # proglog::remove("reader");
+/// ```
+pub fn get_or_init<F>(name: &str, init: F) -> Arc<ProgLog>
+where
+    F: FnOnce() -> ProgLogBuilder,
+{
+    let mut registry = lock(&REGISTRY);
+    if let Some(log) = registry.get(name) {
+        return Arc::clone(log);
+    }
+    let log = Arc::new(init().name(name).build());
+    registry.insert(name.to_owned(), Arc::clone(&log));
+    log
+}
+
+/// Get the registered logger `name`, if any.
+pub fn get(name: &str) -> Option<Arc<ProgLog>> {
+    lock(&REGISTRY).get(name).cloned()
+}
+
+/// All registered loggers, ordered by name.
+///
+/// ```rust
+/// for log in proglog::loggers() {
+///     println!("{}", log.snapshot());
+/// }
+/// ```
+pub fn loggers() -> Vec<Arc<ProgLog>> {
+    lock(&REGISTRY).values().cloned().collect()
+}
+
+/// Remove the logger `name` from the registry, returning it if it was registered.
+///
+/// The logger is flushed and its summary logged when the returned handle, and any others
+/// obtained from the registry, are dropped. Dropping the returned value straight away therefore
+/// finishes the logger if no other handles are alive.
+pub fn remove(name: &str) -> Option<Arc<ProgLog>> {
+    lock(&REGISTRY).remove(name)
+}
@@ -0,0 +1,45 @@
+//! Log the progress of every registered logger on `SIGUSR1`.
+use crate::loggers;
+use log::log;
+use signal_hook::{consts::SIGUSR1, iterator::Signals};
+use std::{io, sync::OnceLock, thread};
+
+static INSTALLED: OnceLock<()> = OnceLock::new();
+
+/// Log a snapshot of every logger in the registry, see [`get_or_init`](crate::get_or_init),
+/// whenever the process receives `SIGUSR1`.
+///
+/// Like `dd`, this lets an operator poke a long-running job for its status with
+/// `kill -USR1 <pid>` without waiting for the next `unit` boundary. Each snapshot is logged at the
+/// level of its logger, muted loggers are skipped.
+///
+/// The signal is handled on a background thread, so it is safe to log from. Calling this again
+/// does nothing.
+pub fn dump_on_signal() -> io::Result<()> {
+    if INSTALLED.get().is_some() {
+        return Ok(());
+    }
+    let mut signals = Signals::new([SIGUSR1])?;
+    if INSTALLED.set(()).is_err() {
+        // Another thread installed the handler first.
+        signals.handle().close();
+        return Ok(());
+    }
+    thread::Builder::new()
+        .name(String::from("proglog-signal"))
+        .spawn(move || {
+            for _ in signals.forever() {
+                dump();
+            }
+        })?;
+    Ok(())
+}
+
+/// Log a snapshot of every registered logger.
+fn dump() {
+    for log in loggers() {
+        if !log.is_muted() {
+            log!(log.level(), "{}", log.snapshot());
+        }
+    }
+}
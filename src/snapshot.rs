@@ -0,0 +1,130 @@
+//! A point-in-time view of a [`ProgLog`](crate::ProgLog).
+use crate::{LatencyStats, TopKeys, ValueStats};
+use std::{
+    fmt::{self, Display},
+    time::Duration,
+};
+
+/// A point-in-time view of the progress of a [`ProgLog`](crate::ProgLog).
+///
+/// Created with [`ProgLog::snapshot`](crate::ProgLog::snapshot). All rates are in items per second.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ProgressSnapshot {
+    /// The name of the logger.
+    pub name: String,
+    /// The noun used by the logger.
+    pub noun: String,
+    /// The verb used by the logger.
+    pub verb: String,
+    /// The number of items seen so far.
+    pub count: u64,
+    /// The current number of items between messages.
+    pub unit: u64,
+    /// The expected total number of items, if known.
+    pub total: Option<u64>,
+    /// Time since the logger was created.
+    pub elapsed: Duration,
+    /// The rate over the lifetime of the logger.
+    pub rate: f64,
+    /// The rate between the two most recently emitted messages.
+    pub recent_rate: Option<f64>,
+    /// The exponentially weighted moving-average rate, see [`ProgLogBuilder::rate_half_life`](crate::ProgLogBuilder::rate_half_life).
+    pub ewma_rate: Option<f64>,
+    /// The estimated time until `total` is reached.
+    pub eta: Option<Duration>,
+    /// The percent of `total` seen so far, or of the weighted total if set.
+    pub percent: Option<f64>,
+    /// The number of log messages emitted so far.
+    pub messages: u64,
+    /// The number of errors seen by [`ProgLog::record_result`](crate::ProgLog::record_result).
+    pub errors: u64,
+    /// The stats of the values given to [`ProgLog::record_value`](crate::ProgLog::record_value), if any.
+    pub values: Option<ValueStats>,
+    /// The latency percentiles of the items timed with [`ProgLog::time`](crate::ProgLog::time), if any.
+    pub latency: Option<LatencyStats>,
+    /// The approximate number of distinct keys given to [`ProgLog::record_key`](crate::ProgLog::record_key), if any.
+    pub distinct: Option<u64>,
+    /// The approximate fraction of keys given to [`ProgLog::record_key`](crate::ProgLog::record_key) that were duplicates.
+    pub duplication: Option<f64>,
+    /// The most frequent keys given to [`ProgLog::record_keyed`](crate::ProgLog::record_keyed), if enabled.
+    pub top_keys: Option<TopKeys>,
+    /// The sum of the weights given to [`ProgLog::record_weighted`](crate::ProgLog::record_weighted),
+    /// if [`ProgLogBuilder::weighted_total`](crate::ProgLogBuilder::weighted_total) is set.
+    pub weight: Option<f64>,
+    /// The name and count of each additional named counter.
+    pub counters: Vec<(String, u64)>,
+}
+
+impl Display for ProgressSnapshot {
+    /// Formats as `[{name}] {verb} {count}/{total} {noun} ({percent}%) in {elapsed}; {rate} {noun}/s ...`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {} {}", self.name, self.verb, self.count)?;
+        if let Some(total) = self.total {
+            write!(f, "/{}", total)?;
+        }
+        write!(f, " {}", self.noun)?;
+        for (i, (name, count)) in self.counters.iter().enumerate() {
+            let sep = if i == 0 { " (" } else { ", " };
+            write!(f, "{}{} {}", sep, name, count)?;
+        }
+        if !self.counters.is_empty() {
+            write!(f, ")")?;
+        }
+        if let Some(percent) = self.percent {
+            write!(f, " ({:.1}%)", percent)?;
+        }
+        write!(
+            f,
+            " in {}; {:.1} {}/s",
+            fmt_duration(self.elapsed),
+            self.rate,
+            self.noun
+        )?;
+        if let (Some(recent_rate), Some(ewma_rate)) = (self.recent_rate, self.ewma_rate) {
+            write!(
+                f,
+                " (recent {:.1} {noun}/s, moving average {:.1} {noun}/s)",
+                recent_rate,
+                ewma_rate,
+                noun = self.noun
+            )?;
+        }
+        if let Some(eta) = self.eta {
+            write!(f, "; ETA {}", fmt_duration(eta))?;
+        }
+        if self.errors > 0 {
+            write!(f, "; {} errors", self.errors)?;
+        }
+        if let Some(values) = &self.values {
+            write!(f, "; values {}", values)?;
+        }
+        if let Some(latency) = &self.latency {
+            write!(f, "; latency {}", latency)?;
+        }
+        if let (Some(distinct), Some(duplication)) = (self.distinct, self.duplication) {
+            write!(
+                f,
+                "; ~{} distinct keys ({:.2}% duplicates)",
+                distinct,
+                duplication * 100.0
+            )?;
+        }
+        if let Some(top_keys) = &self.top_keys {
+            write!(f, "; top keys {}", top_keys)?;
+        }
+        write!(f, "; {} messages", self.messages)
+    }
+}
+
+/// Format a duration as `1.5s`, `2m05s` or `1h02m05s`.
+pub(crate) fn fmt_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs < 60 {
+        format!("{:.1}s", duration.as_secs_f64())
+    } else if secs < 60 * 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h{:02}m{:02}s", secs / 3600, (secs / 60) % 60, secs % 60)
+    }
+}
@@ -0,0 +1,99 @@
+//! Background threads that emit messages independently of the `record` methods.
+use crate::{lock, snapshot::fmt_duration, Inner, MIN_PERIOD};
+use log::warn;
+use std::{
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// Handle to a thread that calls a closure periodically, the thread is stopped and joined on drop.
+pub(crate) struct Ticker {
+    /// Dropping the sender disconnects the channel, which stops the thread.
+    stop: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Ticker {
+    /// Spawn a thread that calls `tick` every `period`.
+    pub(crate) fn spawn(
+        name: String,
+        period: Duration,
+        mut tick: impl FnMut() + Send + 'static,
+    ) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let handle = thread::Builder::new()
+            .name(name)
+            .spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(period) {
+                    tick();
+                }
+            })
+            .expect("Failed to spawn proglog thread");
+        Self {
+            stop: Some(stop),
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for Ticker {
+    /// Stop the thread and wait for it to exit.
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// How often to check on a logger for something that should happen every `period`.
+fn poll_interval(period: Duration) -> Duration {
+    (period / 10).clamp(MIN_PERIOD, Duration::from_secs(1))
+}
+
+/// Warn each time the count has not advanced for `timeout`.
+pub(crate) fn stall_watchdog(inner: Arc<Inner>, timeout: Duration) -> Ticker {
+    let mut last_count = inner.count();
+    let mut last_change = Instant::now();
+    let mut last_warning = last_change;
+    Ticker::spawn(
+        format!("proglog-watchdog-{}", inner.name),
+        poll_interval(timeout),
+        move || {
+            let count = inner.count();
+            let now = Instant::now();
+            if count != last_count {
+                last_count = count;
+                last_change = now;
+                last_warning = now;
+            } else if now.duration_since(last_warning) >= timeout && !inner.is_muted() {
+                last_warning = now;
+                warn!(
+                    "[{}] No progress for {} (stuck at {} {})",
+                    inner.name,
+                    fmt_duration(now.duration_since(last_change)),
+                    inner.fmt_count(count),
+                    inner.noun
+                );
+            }
+        },
+    )
+}
+
+/// Emit a regular progress message whenever no message has been emitted for `period`.
+pub(crate) fn heartbeat(inner: Arc<Inner>, period: Duration) -> Ticker {
+    Ticker::spawn(
+        format!("proglog-heartbeat-{}", inner.name),
+        poll_interval(period),
+        move || {
+            let last_message = lock(&inner.emissions).last_message;
+            if last_message.elapsed() >= period {
+                inner.log_it(inner.count(), inner.level());
+            }
+        },
+    )
+}
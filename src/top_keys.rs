@@ -0,0 +1,160 @@
+//! Heavy-hitter tracking with the Space-Saving algorithm.
+use crate::lock;
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+/// The number of independently locked shards, to keep contention down across threads.
+const SHARDS: usize = 16;
+/// How many counters each shard keeps per requested key, more counters give more accurate counts.
+const COUNTERS_PER_KEY: usize = 4;
+const MIN_COUNTERS: usize = 16;
+
+/// The most frequent keys given to [`ProgLog::record_keyed`](crate::ProgLog::record_keyed).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TopKeys {
+    /// The number of keys seen.
+    pub keyed: u64,
+    /// The most frequent keys and their approximate counts, most frequent first.
+    ///
+    /// Counts may overestimate the true count of a key, but never underestimate it.
+    pub keys: Vec<(String, u64)>,
+}
+
+impl Display for TopKeys {
+    /// Formats as `{key} {count} ({percent}%), ...`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (key, count)) in self.keys.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(
+                f,
+                "{} {} ({:.2}%)",
+                key,
+                count,
+                *count as f64 / self.keyed.max(1) as f64 * 100.0
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Space-Saving counters for one shard of the key space.
+struct SpaceSaving {
+    capacity: usize,
+    counts: HashMap<String, u64>,
+}
+
+impl SpaceSaving {
+    fn record(&mut self, key: &str) {
+        if let Some(count) = self.counts.get_mut(key) {
+            *count += 1;
+        } else if self.counts.len() < self.capacity {
+            self.counts.insert(key.to_owned(), 1);
+        } else {
+            // Replace the least frequent key, inheriting its count as the possible overestimate.
+            let (min_key, min_count) = self
+                .counts
+                .iter()
+                .min_by_key(|(_, count)| **count)
+                .map(|(key, count)| (key.clone(), *count))
+                .expect("capacity is never zero");
+            self.counts.remove(&min_key);
+            self.counts.insert(key.to_owned(), min_count + 1);
+        }
+    }
+}
+
+/// A sharded Space-Saving tracker of the `k` most frequent keys.
+///
+/// Each key always lands in the same shard, so the shards can be merged by concatenation.
+pub(crate) struct TopKeysTracker {
+    k: usize,
+    keyed: AtomicU64,
+    shards: Box<[Mutex<SpaceSaving>]>,
+}
+
+impl TopKeysTracker {
+    pub(crate) fn new(k: usize) -> Self {
+        let capacity = (k * COUNTERS_PER_KEY).max(MIN_COUNTERS);
+        Self {
+            k,
+            keyed: AtomicU64::new(0),
+            shards: (0..SHARDS)
+                .map(|_| {
+                    Mutex::new(SpaceSaving {
+                        capacity,
+                        counts: HashMap::new(),
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// The number of keys tracked.
+    pub(crate) fn k(&self) -> usize {
+        self.k
+    }
+
+    pub(crate) fn record(&self, key: &str) {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let shard = hasher.finish() as usize % SHARDS;
+        lock(&self.shards[shard]).record(key);
+        self.keyed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The `k` most frequent keys seen so far.
+    pub(crate) fn top(&self) -> TopKeys {
+        let mut keys: Vec<(String, u64)> = self
+            .shards
+            .iter()
+            .flat_map(|shard| {
+                lock(shard)
+                    .counts
+                    .iter()
+                    .map(|(key, count)| (key.clone(), *count))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        keys.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        keys.truncate(self.k);
+        TopKeys {
+            keyed: self.keyed.load(Ordering::Relaxed),
+            keys,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_space_saving() {
+        let tracker = TopKeysTracker::new(2);
+        // Far more distinct keys than counters, with two clear heavy hitters.
+        for i in 0..10_000 {
+            tracker.record(&format!("rare{}", i));
+            if i % 2 == 0 {
+                tracker.record("heavy");
+            }
+            if i % 4 == 0 {
+                tracker.record("medium");
+            }
+        }
+        let top = tracker.top();
+        assert_eq!(top.keyed, 17_500);
+        let keys: Vec<&str> = top.keys.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, vec!["heavy", "medium"]);
+        assert!(top.keys[0].1 >= 5_000);
+    }
+}